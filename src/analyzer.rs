@@ -28,6 +28,19 @@ pub enum Status {
     Unknown,
 }
 
+impl Status {
+    /// Every status, for validating names in configuration
+    pub const ALL: &'static [Status] = &[
+        Status::TaskComplete,
+        Status::ReviewComplete,
+        Status::Question,
+        Status::PlanReady,
+        Status::SessionLimitReached,
+        Status::APIError,
+        Status::Unknown,
+    ];
+}
+
 /// Tool classification categories
 pub struct ToolCategories;
 
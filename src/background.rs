@@ -0,0 +1,118 @@
+//! Detached background delivery
+//!
+//! This module hands a hook input to a detached worker process so the hook
+//! invoked by Claude Code can exit immediately. The worker is the same binary
+//! re-executed with the hidden `deliver` subcommand; it receives the hook input
+//! as JSON on stdin and completes delivery with full per-channel timeouts and
+//! retries.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::config::get_state_dir;
+use crate::error::{NotificationError, Result};
+use crate::hooks::HookInput;
+
+/// Name of the hidden subcommand executed by the worker process
+pub const WORKER_SUBCOMMAND: &str = "deliver";
+
+/// Spawn a detached worker process that delivers the given hook input
+///
+/// The worker is placed in its own process group with stdout detached, so it
+/// outlives the hook process and does not hold Claude Code's pipes open.
+/// When debug logging is enabled, the worker's stderr is appended to
+/// `worker.log` in the state directory; otherwise it is discarded.
+pub fn spawn_detached_worker(
+    input: &HookInput,
+    sound: &str,
+    channels: Option<&[String]>,
+    debug: bool,
+) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    let payload = serde_json::to_vec(input)?;
+
+    let mut command = Command::new(exe);
+    command.arg(WORKER_SUBCOMMAND).arg("--sound").arg(sound);
+    if let Some(channels) = channels {
+        command.arg("--channels").arg(channels.join(","));
+    }
+
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(worker_stderr(debug));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Detach from the hook's process group so signals sent to Claude Code's
+        // hook runner do not reach the worker
+        command.process_group(0);
+    }
+
+    let mut child = command.spawn()?;
+    let mut stdin = child.stdin.take().ok_or_else(|| {
+        NotificationError::InvalidInput("Failed to open worker stdin".to_string())
+    })?;
+    stdin.write_all(&payload)?;
+    // Dropping stdin closes the pipe so the worker sees EOF
+
+    crate::debug_context!("background", "Spawned worker process {}", child.id());
+    Ok(())
+}
+
+/// Spawn a detached process that flushes due notifications after a delay
+///
+/// Used to fire escalation steps on time when no daemon is running. The
+/// process sleeps, then runs `flush`, which also cancels steps whose session
+/// became active in the meantime.
+pub fn spawn_flush_timer(delay: Duration, debug: bool) -> Result<()> {
+    let exe = std::env::current_exe()?;
+
+    let mut command = Command::new(exe);
+    command
+        .arg("flush")
+        .arg("--wait-ms")
+        .arg(delay.as_millis().to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(worker_stderr(debug));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let child = command.spawn()?;
+    crate::debug_context!(
+        "background",
+        "Spawned flush timer {} for {:?}",
+        child.id(),
+        delay
+    );
+    Ok(())
+}
+
+/// Open the worker's stderr target (log file in debug mode, null otherwise)
+fn worker_stderr(debug: bool) -> Stdio {
+    if !debug {
+        return Stdio::null();
+    }
+
+    let state_dir = get_state_dir();
+    if fs::create_dir_all(&state_dir).is_err() {
+        return Stdio::null();
+    }
+
+    match OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_dir.join("worker.log"))
+    {
+        Ok(file) => Stdio::from(file),
+        Err(_) => Stdio::null(),
+    }
+}
@@ -12,7 +12,7 @@ use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::channels::r#trait::NotificationChannel;
-use crate::channels::webhook::WebhookClient;
+use crate::channels::webhook::{vendor_error, WebhookClient};
 use crate::config::{ChannelConfig, MessageTemplate, TemplateEngine};
 use crate::error::ChannelError;
 use crate::hooks::HookInput;
@@ -46,6 +46,26 @@ impl DingTalkChannel {
         base64::engine::general_purpose::STANDARD.encode(code)
     }
 
+    /// Interpret the `errcode` DingTalk returns in HTTP 200 responses
+    fn check_response(body: &str) -> Result<(), ChannelError> {
+        let Some((code, message)) = vendor_error(body, &["errcode"], &["errmsg"]) else {
+            return Ok(());
+        };
+        let detail = format!("errcode {}: {}", code, message);
+        let lower = message.to_lowercase();
+
+        Err(match code {
+            // Security settings: keyword, signature/timestamp or IP whitelist mismatch
+            310000 if lower.contains("keyword") => ChannelError::KeywordBlocked(detail),
+            310000 if lower.contains("sign") || lower.contains("timestamp") => {
+                ChannelError::SignatureInvalid(detail)
+            }
+            300001 | 300005 => ChannelError::TokenRevoked(detail),
+            410100 | 130101 => ChannelError::RateLimited(detail),
+            _ => ChannelError::VendorError { code, message },
+        })
+    }
+
     /// Build DingTalk message from hook input and configuration
     fn build_message(
         &self,
@@ -58,11 +78,17 @@ impl DingTalkChannel {
             template_engine.get_template(&input.hook_event_name, config.message_template.as_ref());
         let rendered = template_engine.render(&template, input);
 
+        // Critical notifications mention everyone in the group, if enabled
+        let at = config
+            .mentions_all(input.severity)
+            .then_some(DingTalkAt { is_at_all: true });
+
         Ok(DingTalkMessage {
             msgtype: "text".to_string(),
             text: DingTalkText {
                 content: rendered.body,
             },
+            at,
         })
     }
 }
@@ -109,6 +135,7 @@ impl NotificationChannel for DingTalkChannel {
             .ok_or_else(|| ChannelError::InvalidConfig("webhook_url not configured".to_string()))?;
 
         let message = self.build_message(input, config)?;
+        let client = self.client.for_channel(config)?;
 
         // Check if secret is configured for signing
         if let Some(secret) = &config.secret {
@@ -123,14 +150,17 @@ impl NotificationChannel for DingTalkChannel {
                 // Append timestamp and sign to webhook URL
                 let signed_url = format!("{}&timestamp={}&sign={}", webhook_url, timestamp, sign);
 
-                self.client.send(&signed_url, &message).await?;
-                return Ok(());
+                return client
+                    .send(&signed_url, &message)
+                    .await?
+                    .into_result(Self::check_response);
             }
         }
 
-        self.client.send(webhook_url, &message).await?;
-
-        Ok(())
+        client
+            .send(webhook_url, &message)
+            .await?
+            .into_result(Self::check_response)
     }
 
     async fn test(&self, config: &ChannelConfig) -> Result<String, ChannelError> {
@@ -145,6 +175,7 @@ impl NotificationChannel for DingTalkChannel {
 
         let webhook_url = config.webhook_url.as_ref().unwrap();
         let message = self.build_message(&test_input, config)?;
+        let client = self.client.for_channel(config)?;
 
         // Check if secret is configured
         let url = if let Some(secret) = &config.secret {
@@ -163,17 +194,11 @@ impl NotificationChannel for DingTalkChannel {
             webhook_url.clone()
         };
 
-        let response: crate::channels::webhook::WebhookResponse =
-            self.client.send(&url, &message).await?;
-        if response.is_success() {
-            Ok("DingTalk webhook test successful".to_string())
-        } else {
-            let (code, body) = response.error_info().unwrap();
-            Err(ChannelError::WebhookResponseError(format!(
-                "HTTP {}: {}",
-                code, body
-            )))
-        }
+        client
+            .send(&url, &message)
+            .await?
+            .into_result(Self::check_response)?;
+        Ok("DingTalk webhook test successful".to_string())
     }
 }
 
@@ -182,6 +207,8 @@ impl NotificationChannel for DingTalkChannel {
 struct DingTalkMessage {
     msgtype: String,
     text: DingTalkText,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    at: Option<DingTalkAt>,
 }
 
 /// DingTalk text message content
@@ -190,9 +217,17 @@ struct DingTalkText {
     content: String,
 }
 
+/// DingTalk mentions
+#[derive(Debug, Serialize)]
+struct DingTalkAt {
+    #[serde(rename = "isAtAll")]
+    is_at_all: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::severity::Severity;
 
     #[test]
     fn test_channel_type() {
@@ -240,6 +275,35 @@ mod tests {
         assert!(signature.len() > 20);
     }
 
+    #[test]
+    fn test_check_response() {
+        assert!(DingTalkChannel::check_response(r#"{"errcode":0,"errmsg":"ok"}"#).is_ok());
+        assert!(matches!(
+            DingTalkChannel::check_response(
+                r#"{"errcode":310000,"errmsg":"keywords not in content"}"#
+            ),
+            Err(ChannelError::KeywordBlocked(_))
+        ));
+        assert!(matches!(
+            DingTalkChannel::check_response(r#"{"errcode":310000,"errmsg":"sign not match"}"#),
+            Err(ChannelError::SignatureInvalid(_))
+        ));
+        assert!(matches!(
+            DingTalkChannel::check_response(r#"{"errcode":300001,"errmsg":"token is not exist"}"#),
+            Err(ChannelError::TokenRevoked(_))
+        ));
+        assert!(matches!(
+            DingTalkChannel::check_response(r#"{"errcode":410100,"errmsg":"send too fast"}"#),
+            Err(ChannelError::RateLimited(_))
+        ));
+        assert!(matches!(
+            DingTalkChannel::check_response(
+                r#"{"errcode":310000,"errmsg":"ip 1.2.3.4 not in whitelist"}"#
+            ),
+            Err(ChannelError::VendorError { code: 310000, .. })
+        ));
+    }
+
     #[test]
     fn test_build_message() {
         let channel = DingTalkChannel::new();
@@ -265,5 +329,23 @@ mod tests {
         let message = channel.build_message(&input, &config).unwrap();
         assert_eq!(message.msgtype, "text");
         assert_eq!(message.text.content, "Test: Hello DingTalk");
+        assert!(message.at.is_none());
+
+        let critical = HookInput {
+            severity: Some(Severity::Critical),
+            ..input
+        };
+        let message = channel.build_message(&critical, &config).unwrap();
+        assert!(message.at.is_none());
+
+        let config = ChannelConfig {
+            mention_all_on_critical: true,
+            ..config
+        };
+        let message = channel.build_message(&critical, &config).unwrap();
+        assert_eq!(
+            serde_json::to_value(&message).unwrap()["at"],
+            serde_json::json!({"isAtAll": true})
+        );
     }
 }
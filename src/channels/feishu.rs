@@ -9,7 +9,7 @@ use serde::Serialize;
 use std::collections::HashMap;
 
 use crate::channels::r#trait::NotificationChannel;
-use crate::channels::webhook::WebhookClient;
+use crate::channels::webhook::{vendor_error, WebhookClient};
 use crate::config::{ChannelConfig, MessageTemplate, TemplateEngine};
 use crate::error::ChannelError;
 use crate::hooks::HookInput;
@@ -26,6 +26,26 @@ impl FeishuChannel {
         }
     }
 
+    /// Interpret the `code` Feishu returns in HTTP 200 responses
+    ///
+    /// Older webhook versions report `StatusCode`/`StatusMessage` instead.
+    fn check_response(body: &str) -> Result<(), ChannelError> {
+        let Some((code, message)) =
+            vendor_error(body, &["code", "StatusCode"], &["msg", "StatusMessage"])
+        else {
+            return Ok(());
+        };
+        let detail = format!("code {}: {}", code, message);
+
+        Err(match code {
+            19021 => ChannelError::SignatureInvalid(detail),
+            19024 => ChannelError::KeywordBlocked(detail),
+            19001 => ChannelError::TokenRevoked(detail),
+            11232 => ChannelError::RateLimited(detail),
+            _ => ChannelError::VendorError { code, message },
+        })
+    }
+
     /// Build Feishu message from hook input and configuration
     fn build_message(
         &self,
@@ -38,11 +58,16 @@ impl FeishuChannel {
             template_engine.get_template(&input.hook_event_name, config.message_template.as_ref());
         let rendered = template_engine.render(&template, input);
 
+        // Critical notifications mention everyone in the group, if enabled
+        let text = if config.mentions_all(input.severity) {
+            format!("{}\n<at user_id=\"all\">所有人</at>", rendered.body)
+        } else {
+            rendered.body
+        };
+
         Ok(FeishuMessage {
             msg_type: "text".to_string(),
-            content: FeishuContent {
-                text: rendered.body,
-            },
+            content: FeishuContent { text },
         })
     }
 }
@@ -89,10 +114,12 @@ impl NotificationChannel for FeishuChannel {
             .ok_or_else(|| ChannelError::InvalidConfig("webhook_url not configured".to_string()))?;
 
         let message = self.build_message(input, config)?;
+        let client = self.client.for_channel(config)?;
 
-        self.client.send(url, &message).await?;
-
-        Ok(())
+        client
+            .send(url, &message)
+            .await?
+            .into_result(Self::check_response)
     }
 
     async fn test(&self, config: &ChannelConfig) -> Result<String, ChannelError> {
@@ -107,18 +134,13 @@ impl NotificationChannel for FeishuChannel {
 
         let url = config.webhook_url.as_ref().unwrap();
         let message = self.build_message(&test_input, config)?;
+        let client = self.client.for_channel(config)?;
 
-        let response: crate::channels::webhook::WebhookResponse =
-            self.client.send(url, &message).await?;
-        if response.is_success() {
-            Ok("Feishu webhook test successful".to_string())
-        } else {
-            let (code, body) = response.error_info().unwrap();
-            Err(ChannelError::WebhookResponseError(format!(
-                "HTTP {}: {}",
-                code, body
-            )))
-        }
+        client
+            .send(url, &message)
+            .await?
+            .into_result(Self::check_response)?;
+        Ok("Feishu webhook test successful".to_string())
     }
 }
 
@@ -138,6 +160,7 @@ struct FeishuContent {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::severity::Severity;
 
     #[test]
     fn test_channel_type() {
@@ -174,6 +197,32 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_check_response() {
+        assert!(FeishuChannel::check_response(r#"{"code":0,"msg":"success"}"#).is_ok());
+        assert!(
+            FeishuChannel::check_response(r#"{"StatusCode":0,"StatusMessage":"success"}"#).is_ok()
+        );
+        assert!(matches!(
+            FeishuChannel::check_response(r#"{"code":19021,"msg":"sign match fail"}"#),
+            Err(ChannelError::SignatureInvalid(_))
+        ));
+        assert!(matches!(
+            FeishuChannel::check_response(r#"{"code":19024,"msg":"Key Words Not Found"}"#),
+            Err(ChannelError::KeywordBlocked(_))
+        ));
+        assert!(matches!(
+            FeishuChannel::check_response(
+                r#"{"code":19001,"msg":"param invalid: incoming webhook access token invalid"}"#
+            ),
+            Err(ChannelError::TokenRevoked(_))
+        ));
+        assert!(matches!(
+            FeishuChannel::check_response(r#"{"code":11232,"msg":"frequency limited"}"#),
+            Err(ChannelError::RateLimited(_))
+        ));
+    }
+
     #[test]
     fn test_build_message() {
         let channel = FeishuChannel::new();
@@ -199,5 +248,22 @@ mod tests {
         let message = channel.build_message(&input, &config).unwrap();
         assert_eq!(message.msg_type, "text");
         assert_eq!(message.content.text, "Test: Hello Feishu");
+
+        let critical = HookInput {
+            severity: Some(Severity::Critical),
+            ..input
+        };
+        let message = channel.build_message(&critical, &config).unwrap();
+        assert_eq!(message.content.text, "Test: Hello Feishu");
+
+        let config = ChannelConfig {
+            mention_all_on_critical: true,
+            ..config
+        };
+        let message = channel.build_message(&critical, &config).unwrap();
+        assert!(message
+            .content
+            .text
+            .ends_with("<at user_id=\"all\">所有人</at>"));
     }
 }
@@ -18,13 +18,18 @@ pub use webhook::WebhookClient;
 pub use wechat::WeChatChannel;
 
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 /// Channel factory function type
 type ChannelFactory = Box<dyn Fn() -> Box<dyn NotificationChannel + Send + Sync> + Send + Sync>;
 
+/// Channel instance shared between send tasks
+pub type SharedChannel = Arc<dyn NotificationChannel + Send + Sync>;
+
 /// Channel registry for managing available notification channels
 pub struct ChannelRegistry {
     factories: HashMap<String, ChannelFactory>,
+    instances: Mutex<HashMap<String, SharedChannel>>,
 }
 
 impl ChannelRegistry {
@@ -32,6 +37,7 @@ impl ChannelRegistry {
     pub fn new() -> Self {
         let mut registry = Self {
             factories: HashMap::new(),
+            instances: Mutex::new(HashMap::new()),
         };
 
         // Register built-in channels
@@ -64,7 +70,7 @@ impl ChannelRegistry {
     }
 
     /// Register a channel factory
-    fn register_factory<F>(&mut self, channel_type: &str, factory: F)
+    pub(crate) fn register_factory<F>(&mut self, channel_type: &str, factory: F)
     where
         F: Fn() -> Box<dyn NotificationChannel + Send + Sync> + Send + Sync + 'static,
     {
@@ -80,6 +86,21 @@ impl ChannelRegistry {
         self.factories.get(channel_type).map(|factory| factory())
     }
 
+    /// Get the shared channel instance for a type, creating it on first use
+    ///
+    /// Channels take their settings from the config passed to each send, so a
+    /// single instance per type serves every configured channel of that type.
+    pub fn get_channel(&self, channel_type: &str) -> Option<SharedChannel> {
+        let mut instances = self.instances.lock().unwrap();
+        if let Some(channel) = instances.get(channel_type) {
+            return Some(Arc::clone(channel));
+        }
+
+        let channel: SharedChannel = Arc::from(self.create_channel(channel_type)?);
+        instances.insert(channel_type.to_string(), Arc::clone(&channel));
+        Some(channel)
+    }
+
     /// List all registered channel types
     pub fn list_channels(&self) -> Vec<&str> {
         self.factories.keys().map(|k| k.as_str()).collect()
@@ -112,4 +133,14 @@ mod tests {
         assert!(registry.create_channel("dingtalk").is_some());
         assert!(registry.create_channel("nonexistent").is_none());
     }
+
+    #[test]
+    fn test_get_channel_reuses_instance() {
+        let registry = ChannelRegistry::new();
+
+        let first = registry.get_channel("dingtalk").unwrap();
+        let second = registry.get_channel("dingtalk").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(registry.get_channel("nonexistent").is_none());
+    }
 }
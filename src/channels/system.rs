@@ -18,6 +18,7 @@ use crate::config::templates::TemplateEngine;
 use crate::config::ChannelConfig;
 use crate::error::{ChannelError, NotificationError};
 use crate::hooks::HookInput;
+use crate::severity::Severity;
 
 use crate::{debug_context, debug_log};
 
@@ -113,7 +114,11 @@ impl SystemChannel {
     fn display_notification(
         title: &str,
         body: &str,
-        _timeout_ms: u64, // macOS doesn't support timeout
+        // macOS doesn't support timeout
+        #[cfg_attr(target_os = "macos", allow(unused_variables))] timeout_ms: u64,
+        // Only Linux and the BSDs support urgency
+        #[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(unused_variables))]
+        severity: Severity,
         _icon: Option<&str>, // Icon parameter kept for API compatibility but not used
     ) -> Result<(), NotificationError> {
         debug_context!("system", "Preparing notification");
@@ -152,6 +157,12 @@ impl SystemChannel {
             notification.summary(title);
             notification.body(body);
             notification.timeout(notify_rust::Timeout::Milliseconds(timeout_ms as u32));
+            #[cfg(unix)]
+            notification.urgency(match severity {
+                Severity::Info => notify_rust::Urgency::Low,
+                Severity::Notice | Severity::Warning => notify_rust::Urgency::Normal,
+                Severity::Critical => notify_rust::Urgency::Critical,
+            });
 
             debug_log!("Showing notification...");
             let result = notification.show();
@@ -214,7 +225,8 @@ impl NotificationChannel for SystemChannel {
 
         // Display notification (icon is handled by set_application in new())
         debug_context!("system", "Calling display_notification()...");
-        Self::display_notification(&rendered.title, &rendered.body, timeout_ms, None)
+        let severity = input.severity.unwrap_or_default();
+        Self::display_notification(&rendered.title, &rendered.body, timeout_ms, severity, None)
             .map_err(|e| ChannelError::InvalidConfig(e.to_string()))?;
 
         debug_context!(
@@ -304,6 +316,7 @@ mod tests {
     async fn test_validate_config() {
         let channel = SystemChannel::new();
 
+        #[cfg_attr(not(target_os = "macos"), allow(unused_variables))]
         let config_enabled = ChannelConfig {
             enabled: true,
             sound: Some("Glass".to_string()),
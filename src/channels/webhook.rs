@@ -1,14 +1,27 @@
 //! Generic webhook client for sending notifications
 //!
 //! This module provides a reusable HTTP client for webhook-based notifications
-//! with support for custom headers and timeout configuration.
+//! with support for custom headers, timeouts, proxies and TLS settings.
+//!
+//! The underlying `reqwest` clients are pooled for the whole process, one per
+//! combination of connection settings, so channels that fan out from a single
+//! hook share connections instead of repeating TLS handshakes.
 
-use reqwest::Client;
+use reqwest::{Certificate, Client, Identity, NoProxy, Proxy};
 use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 
+use crate::config::ChannelConfig;
 use crate::error::{ChannelError, NotificationError};
 
+/// Default time allowed for a whole webhook request
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// HTTP clients shared by all webhook channels, keyed by connection settings
+static CLIENT_POOL: OnceLock<Mutex<HashMap<ClientProfile, Client>>> = OnceLock::new();
+
 /// Generic webhook client for HTTP-based notifications
 pub struct WebhookClient {
     client: Client,
@@ -18,27 +31,13 @@ pub struct WebhookClient {
 impl WebhookClient {
     /// Create a new webhook client with default timeout (3 seconds for faster response)
     pub fn new() -> Result<Self, NotificationError> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(3))
-            .build()
-            .map_err(|e| {
-                NotificationError::WebhookError(format!("Failed to create HTTP client: {}", e))
-            })?;
-
-        Ok(Self {
-            client,
-            timeout: Duration::from_secs(3),
-        })
+        Self::with_timeout(DEFAULT_TIMEOUT.as_secs())
     }
 
     /// Create a new webhook client with custom timeout
     pub fn with_timeout(timeout_secs: u64) -> Result<Self, NotificationError> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(timeout_secs))
-            .build()
-            .map_err(|e| {
-                NotificationError::WebhookError(format!("Failed to create HTTP client: {}", e))
-            })?;
+        let client = pooled_client(&ClientProfile::default())
+            .map_err(|e| NotificationError::WebhookError(e.to_string()))?;
 
         Ok(Self {
             client,
@@ -46,6 +45,22 @@ impl WebhookClient {
         })
     }
 
+    /// Apply a channel's timeout, proxy and TLS settings
+    ///
+    /// The request timeout is set per request; the other settings select the
+    /// pooled HTTP client built for them.
+    pub fn for_channel(&self, config: &ChannelConfig) -> Result<Self, ChannelError> {
+        let timeout = config
+            .request_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(self.timeout);
+
+        Ok(Self {
+            client: pooled_client(&ClientProfile::from_config(config))?,
+            timeout,
+        })
+    }
+
     /// Send POST request to webhook URL
     pub async fn send<T: Serialize>(
         &self,
@@ -129,6 +144,23 @@ pub enum WebhookResponse {
 }
 
 impl WebhookResponse {
+    /// Convert the response into a channel result
+    ///
+    /// Non-2xx responses are errors. Vendors also report failures in the body
+    /// of a 200 OK response, so `check_body` inspects successful bodies.
+    pub fn into_result(
+        self,
+        check_body: impl FnOnce(&str) -> Result<(), ChannelError>,
+    ) -> Result<(), ChannelError> {
+        match self {
+            Self::Success(body) => check_body(&body),
+            Self::Error(code, body) => Err(ChannelError::WebhookResponseError(format!(
+                "HTTP {}: {}",
+                code, body
+            ))),
+        }
+    }
+
     /// Check if response was successful
     pub fn is_success(&self) -> bool {
         matches!(self, Self::Success(_))
@@ -151,6 +183,163 @@ impl WebhookResponse {
     }
 }
 
+/// Connection settings that need their own HTTP client
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct ClientProfile {
+    connect_timeout_ms: Option<u64>,
+    proxy: Option<String>,
+    no_proxy: Vec<String>,
+    ca_bundle: Option<String>,
+    client_cert: Option<String>,
+    client_key: Option<String>,
+    insecure_skip_verify: bool,
+}
+
+impl ClientProfile {
+    fn from_config(config: &ChannelConfig) -> Self {
+        Self {
+            connect_timeout_ms: config.connect_timeout_ms,
+            proxy: config.proxy.clone(),
+            no_proxy: config.no_proxy.clone(),
+            ca_bundle: config.ca_bundle.clone(),
+            client_cert: config.client_cert.clone(),
+            client_key: config.client_key.clone(),
+            insecure_skip_verify: config.insecure_skip_verify,
+        }
+    }
+}
+
+/// Get the pooled client for a profile, building it on first use
+fn pooled_client(profile: &ClientProfile) -> Result<Client, ChannelError> {
+    let mut clients = CLIENT_POOL.get_or_init(Default::default).lock().unwrap();
+    if let Some(client) = clients.get(profile) {
+        return Ok(client.clone());
+    }
+
+    let client = build_client(profile)?;
+    clients.insert(profile.clone(), client.clone());
+    Ok(client)
+}
+
+/// Build an HTTP client with a profile's connection, proxy and TLS settings
+///
+/// No overall timeout is set here, since each request sets its own.
+fn build_client(config: &ClientProfile) -> Result<Client, ChannelError> {
+    let mut builder = Client::builder();
+
+    if let Some(connect_ms) = config.connect_timeout_ms {
+        builder = builder.connect_timeout(Duration::from_millis(connect_ms));
+    }
+
+    for proxy in channel_proxies(config)? {
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(path) = &config.ca_bundle {
+        let certs = Certificate::from_pem_bundle(&read_pem(path)?).map_err(|e| {
+            ChannelError::InvalidConfig(format!("Invalid CA bundle {}: {}", path, e))
+        })?;
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    if let Some(cert_path) = &config.client_cert {
+        let cert = read_pem(cert_path)?;
+        let key = match &config.client_key {
+            Some(key_path) => read_pem(key_path)?,
+            None => cert.clone(),
+        };
+        let identity = Identity::from_pkcs8_pem(&cert, &key).map_err(|e| {
+            ChannelError::InvalidConfig(format!("Invalid client certificate: {}", e))
+        })?;
+        builder = builder.identity(identity);
+    }
+
+    if config.insecure_skip_verify {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    builder
+        .build()
+        .map_err(|e| ChannelError::HttpError(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// Proxies for a channel's webhook requests
+///
+/// A configured `proxy` takes precedence over the environment. Without one,
+/// reqwest reads HTTPS_PROXY, HTTP_PROXY and ALL_PROXY itself; the variables
+/// are only resolved here when a `no_proxy` list must be applied to them.
+fn channel_proxies(config: &ClientProfile) -> Result<Vec<Proxy>, ChannelError> {
+    let no_proxy = if config.no_proxy.is_empty() {
+        NoProxy::from_env()
+    } else {
+        NoProxy::from_string(&config.no_proxy.join(","))
+    };
+    let invalid = |url: &str, e: reqwest::Error| {
+        ChannelError::InvalidConfig(format!("Invalid proxy {}: {}", url, e))
+    };
+
+    if let Some(url) = &config.proxy {
+        let proxy = Proxy::all(url.as_str()).map_err(|e| invalid(url, e))?;
+        return Ok(vec![proxy.no_proxy(no_proxy)]);
+    }
+    if config.no_proxy.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut proxies = Vec::new();
+    if let Some(url) = env_var(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]) {
+        let proxy = Proxy::https(url.as_str()).map_err(|e| invalid(&url, e))?;
+        proxies.push(proxy.no_proxy(no_proxy.clone()));
+    }
+    if let Some(url) = env_var(&["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]) {
+        let proxy = Proxy::http(url.as_str()).map_err(|e| invalid(&url, e))?;
+        proxies.push(proxy.no_proxy(no_proxy));
+    }
+    Ok(proxies)
+}
+
+/// First non-empty environment variable among `names`
+fn env_var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+}
+
+/// Read a PEM file named in the channel configuration
+fn read_pem(path: &str) -> Result<Vec<u8>, ChannelError> {
+    let expanded = shellexpand::tilde(path);
+    std::fs::read(expanded.as_ref())
+        .map_err(|e| ChannelError::InvalidConfig(format!("Cannot read {}: {}", expanded, e)))
+}
+
+/// Read a vendor's error code and message from a JSON response body
+///
+/// The first of `code_keys` present in the body is used. Returns `None` when
+/// the body is not JSON or reports success (code 0), since some webhooks
+/// answer with plain text like "ok".
+pub fn vendor_error(
+    body: &str,
+    code_keys: &[&str],
+    message_keys: &[&str],
+) -> Option<(i64, String)> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let code = code_keys
+        .iter()
+        .find_map(|key| value.get(*key).and_then(|c| c.as_i64()))?;
+    if code == 0 {
+        return None;
+    }
+
+    let message = message_keys
+        .iter()
+        .find_map(|key| value.get(*key).and_then(|m| m.as_str()))
+        .unwrap_or_default()
+        .to_string();
+    Some((code, message))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,6 +356,74 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_for_channel_timeouts() {
+        let client = WebhookClient::new().unwrap();
+
+        let defaults = client.for_channel(&ChannelConfig::default()).unwrap();
+        assert_eq!(defaults.timeout, Duration::from_secs(3));
+
+        let config = ChannelConfig {
+            connect_timeout_ms: Some(1500),
+            request_timeout_ms: Some(8000),
+            ..Default::default()
+        };
+        let configured = client.for_channel(&config).unwrap();
+        assert_eq!(configured.timeout, Duration::from_millis(8000));
+    }
+
+    #[test]
+    fn test_channel_proxies() {
+        let config = ClientProfile {
+            proxy: Some("socks5://127.0.0.1:1080".to_string()),
+            no_proxy: vec!["internal.example.com".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(channel_proxies(&config).unwrap().len(), 1);
+
+        let config = ClientProfile {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            channel_proxies(&config),
+            Err(ChannelError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_for_channel_tls_settings() {
+        let client = WebhookClient::new().unwrap();
+
+        let config = ChannelConfig {
+            insecure_skip_verify: true,
+            ..Default::default()
+        };
+        assert!(client.for_channel(&config).is_ok());
+
+        let config = ChannelConfig {
+            ca_bundle: Some("/nonexistent/ca.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            client.for_channel(&config),
+            Err(ChannelError::InvalidConfig(msg)) if msg.contains("/nonexistent/ca.pem")
+        ));
+    }
+
+    #[test]
+    fn test_pooled_client_per_profile() {
+        let profile = ClientProfile {
+            connect_timeout_ms: Some(1234),
+            ..Default::default()
+        };
+        pooled_client(&profile).unwrap();
+        pooled_client(&profile).unwrap();
+
+        let pool = CLIENT_POOL.get().unwrap().lock().unwrap();
+        assert_eq!(pool.keys().filter(|p| **p == profile).count(), 1);
+    }
+
     #[test]
     fn test_webhook_response() {
         let success = WebhookResponse::Success("OK".to_string());
@@ -179,4 +436,34 @@ mod tests {
         assert_eq!(error.body_ok(), None);
         assert_eq!(error.error_info(), Some((404, "Not Found")));
     }
+
+    #[test]
+    fn test_into_result() {
+        let success = WebhookResponse::Success("ok".to_string());
+        assert!(success.into_result(|_| Ok(())).is_ok());
+
+        let error = WebhookResponse::Error(503, "Unavailable".to_string());
+        assert!(matches!(
+            error.into_result(|_| Ok(())),
+            Err(ChannelError::WebhookResponseError(msg)) if msg == "HTTP 503: Unavailable"
+        ));
+    }
+
+    #[test]
+    fn test_vendor_error() {
+        let keys = (&["errcode"][..], &["errmsg"][..]);
+        assert_eq!(
+            vendor_error(r#"{"errcode":0,"errmsg":"ok"}"#, keys.0, keys.1),
+            None
+        );
+        assert_eq!(vendor_error("ok", keys.0, keys.1), None);
+        assert_eq!(
+            vendor_error(
+                r#"{"errcode":93000,"errmsg":"invalid webhook url"}"#,
+                keys.0,
+                keys.1
+            ),
+            Some((93000, "invalid webhook url".to_string()))
+        );
+    }
 }
@@ -9,7 +9,7 @@ use serde::Serialize;
 use std::collections::HashMap;
 
 use crate::channels::r#trait::NotificationChannel;
-use crate::channels::webhook::WebhookClient;
+use crate::channels::webhook::{vendor_error, WebhookClient};
 use crate::config::{ChannelConfig, MessageTemplate, TemplateEngine};
 use crate::error::ChannelError;
 use crate::hooks::HookInput;
@@ -26,6 +26,21 @@ impl WeChatChannel {
         }
     }
 
+    /// Interpret the `errcode` WeChat Work returns in HTTP 200 responses
+    fn check_response(body: &str) -> Result<(), ChannelError> {
+        let Some((code, message)) = vendor_error(body, &["errcode"], &["errmsg"]) else {
+            return Ok(());
+        };
+        let detail = format!("errcode {}: {}", code, message);
+
+        Err(match code {
+            // The webhook key is wrong or the robot was removed from the group
+            93000 => ChannelError::TokenRevoked(detail),
+            45009 => ChannelError::RateLimited(detail),
+            _ => ChannelError::VendorError { code, message },
+        })
+    }
+
     /// Build WeChat message from hook input and configuration
     fn build_message(
         &self,
@@ -39,12 +54,17 @@ impl WeChatChannel {
         let rendered = template_engine.render(&template, input);
 
         // Get mentioned_list from template if available
-        let mentioned_list = config
+        let mut mentioned_list = config
             .message_template
             .as_ref()
             .and_then(|t| t.mentioned_list.clone())
             .unwrap_or_default();
 
+        // Critical notifications mention everyone in the group, if enabled
+        if config.mentions_all(input.severity) && !mentioned_list.iter().any(|m| m == "@all") {
+            mentioned_list.push("@all".to_string());
+        }
+
         Ok(WeChatMessage {
             msgtype: "text".to_string(),
             text: WeChatText {
@@ -97,10 +117,12 @@ impl NotificationChannel for WeChatChannel {
             .ok_or_else(|| ChannelError::InvalidConfig("webhook_url not configured".to_string()))?;
 
         let message = self.build_message(input, config)?;
+        let client = self.client.for_channel(config)?;
 
-        self.client.send(url, &message).await?;
-
-        Ok(())
+        client
+            .send(url, &message)
+            .await?
+            .into_result(Self::check_response)
     }
 
     async fn test(&self, config: &ChannelConfig) -> Result<String, ChannelError> {
@@ -115,18 +137,13 @@ impl NotificationChannel for WeChatChannel {
 
         let url = config.webhook_url.as_ref().unwrap();
         let message = self.build_message(&test_input, config)?;
+        let client = self.client.for_channel(config)?;
 
-        let response: crate::channels::webhook::WebhookResponse =
-            self.client.send(url, &message).await?;
-        if response.is_success() {
-            Ok("WeChat Work webhook test successful".to_string())
-        } else {
-            let (code, body) = response.error_info().unwrap();
-            Err(ChannelError::WebhookResponseError(format!(
-                "HTTP {}: {}",
-                code, body
-            )))
-        }
+        client
+            .send(url, &message)
+            .await?
+            .into_result(Self::check_response)?;
+        Ok("WeChat Work webhook test successful".to_string())
     }
 }
 
@@ -148,6 +165,7 @@ struct WeChatText {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::severity::Severity;
 
     #[test]
     fn test_channel_type() {
@@ -186,6 +204,23 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_check_response() {
+        assert!(WeChatChannel::check_response(r#"{"errcode":0,"errmsg":"ok"}"#).is_ok());
+        assert!(matches!(
+            WeChatChannel::check_response(r#"{"errcode":93000,"errmsg":"invalid webhook url"}"#),
+            Err(ChannelError::TokenRevoked(_))
+        ));
+        assert!(matches!(
+            WeChatChannel::check_response(r#"{"errcode":45009,"errmsg":"api freq out of limit"}"#),
+            Err(ChannelError::RateLimited(_))
+        ));
+        assert!(matches!(
+            WeChatChannel::check_response(r#"{"errcode":44004,"errmsg":"empty content"}"#),
+            Err(ChannelError::VendorError { code: 44004, .. })
+        ));
+    }
+
     #[test]
     fn test_build_message() {
         let channel = WeChatChannel::new();
@@ -211,5 +246,19 @@ mod tests {
         let message = channel.build_message(&input, &config).unwrap();
         assert_eq!(message.msgtype, "text");
         assert_eq!(message.text.content, "Test: Hello WeChat");
+
+        let critical = HookInput {
+            severity: Some(Severity::Critical),
+            ..input
+        };
+        let message = channel.build_message(&critical, &config).unwrap();
+        assert!(message.text.mentioned_list.is_empty());
+
+        let config = ChannelConfig {
+            mention_all_on_critical: true,
+            ..config
+        };
+        let message = channel.build_message(&critical, &config).unwrap();
+        assert!(message.text.mentioned_list.contains(&"@all".to_string()));
     }
 }
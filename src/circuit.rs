@@ -0,0 +1,363 @@
+//! Circuit breaker for persistently failing channels
+//!
+//! A channel that keeps failing (a revoked webhook token, an unreachable
+//! endpoint) would otherwise add its full timeout to every hook invocation.
+//! Consecutive failures are counted per channel in the runtime state; once
+//! they reach the threshold the circuit opens and the channel is skipped until
+//! the cooldown has passed. After the cooldown a single probe send is let
+//! through, which either closes the circuit or keeps it open for another
+//! cooldown. Sends still in flight are tracked separately and only count as
+//! failures if they report an error or are abandoned when the hook's time
+//! budget runs out.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::channels::ChannelResult;
+use crate::config::CircuitBreakerConfig;
+use crate::state::StateStore;
+
+/// Failure tracking for a single channel
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CircuitState {
+    /// Sends since the last success that failed or were abandoned
+    pub consecutive_failures: u32,
+    /// Time of the most recent failure in milliseconds since the Unix epoch
+    pub last_failure_at_ms: i64,
+    /// Sends started but not yet finished
+    pub in_flight: u32,
+    /// Error reported by the most recent failed send
+    pub last_error: Option<String>,
+}
+
+impl CircuitState {
+    /// Time the open circuit closes again, or `None` while it is closed
+    pub fn open_until_ms(&self, settings: &CircuitBreakerConfig, now_ms: i64) -> Option<i64> {
+        if !settings.enabled || self.consecutive_failures < settings.failure_threshold {
+            return None;
+        }
+        let until = self.last_failure_at_ms + settings.cooldown_secs as i64 * 1000;
+        (now_ms < until).then_some(until)
+    }
+
+    /// Start a send; returns `false` when the circuit is open and the send
+    /// should be skipped
+    ///
+    /// A send let through after the cooldown is the probe: it restarts the
+    /// cooldown so that concurrent sends are held back until it finishes.
+    pub fn begin(&mut self, settings: &CircuitBreakerConfig, now_ms: i64) -> bool {
+        if self.open_until_ms(settings, now_ms).is_some() {
+            return false;
+        }
+        if settings.enabled && self.consecutive_failures >= settings.failure_threshold {
+            self.last_failure_at_ms = now_ms;
+        }
+        self.in_flight += 1;
+        true
+    }
+
+    /// Record the outcome of a send started with [`begin`](Self::begin)
+    pub fn finish<T>(&mut self, result: &ChannelResult<T>, now_ms: i64) {
+        self.in_flight = self.in_flight.saturating_sub(1);
+        match result {
+            Ok(_) => {
+                self.consecutive_failures = 0;
+                self.last_failure_at_ms = 0;
+                self.last_error = None;
+            }
+            Err(e) => self.fail(e.to_string(), now_ms),
+        }
+    }
+
+    /// Record a send that was cut off before it reported an outcome
+    pub fn abandon(&mut self, now_ms: i64) {
+        self.in_flight = self.in_flight.saturating_sub(1);
+        self.fail(ABANDONED_ERROR.to_string(), now_ms);
+    }
+
+    /// Whether the channel needs no persisted state
+    fn is_idle(&self) -> bool {
+        self.consecutive_failures == 0 && self.in_flight == 0
+    }
+
+    fn fail(&mut self, error: String, now_ms: i64) {
+        self.consecutive_failures += 1;
+        self.last_failure_at_ms = now_ms;
+        self.last_error = Some(error);
+    }
+}
+
+/// Error recorded for a send cut off by the dispatch budget
+const ABANDONED_ERROR: &str = "Dispatch budget ran out before the send finished";
+
+/// Persisted circuit breaker shared by the send tasks of a channel manager
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    store: StateStore,
+    settings: CircuitBreakerConfig,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker backed by a state store
+    pub fn new(store: StateStore, settings: CircuitBreakerConfig) -> Self {
+        Self { store, settings }
+    }
+
+    /// Check whether a channel's circuit is open without starting a send
+    pub fn is_open(&self, channel_id: &str) -> bool {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        self.settings.enabled
+            && self.store.load().is_ok_and(|state| {
+                state
+                    .circuits
+                    .get(channel_id)
+                    .and_then(|circuit| circuit.open_until_ms(&self.settings, now_ms))
+                    .is_some()
+            })
+    }
+
+    /// Start a send on a channel; returns `None` if its circuit is open
+    ///
+    /// If the state file cannot be accessed, the send goes ahead.
+    pub fn begin(&self, channel_id: &str) -> Option<CircuitSend> {
+        let started = !self.settings.enabled || {
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            self.store
+                .update(|state| {
+                    state
+                        .circuits
+                        .entry(channel_id.to_string())
+                        .or_default()
+                        .begin(&self.settings, now_ms)
+                })
+                .unwrap_or_else(|e| {
+                    eprintln!(
+                        "Warning: Circuit state unavailable for {}: {}",
+                        channel_id, e
+                    );
+                    true
+                })
+        };
+        started.then(|| CircuitSend {
+            breaker: self.clone(),
+            channel_id: Some(channel_id.to_string()),
+        })
+    }
+
+    /// Update a channel's circuit after one of its sends ended
+    fn record(&self, channel_id: &str, update: impl FnOnce(&mut CircuitState, i64)) {
+        if !self.settings.enabled {
+            return;
+        }
+
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let result = self.store.update(|state| {
+            let circuit = state.circuits.entry(channel_id.to_string()).or_default();
+            update(circuit, now_ms);
+            // Closed circuits need no state
+            if circuit.is_idle() {
+                state.circuits.remove(channel_id);
+            }
+        });
+        if let Err(e) = result {
+            eprintln!(
+                "Warning: Could not record circuit state for {}: {}",
+                channel_id, e
+            );
+        }
+    }
+}
+
+/// A send let through by the circuit breaker
+///
+/// Dropping it without [`finish`](Self::finish), for example when the
+/// dispatch budget runs out, counts the send as a failure.
+#[derive(Debug)]
+pub struct CircuitSend {
+    breaker: CircuitBreaker,
+    channel_id: Option<String>,
+}
+
+impl CircuitSend {
+    /// Record the outcome of the send
+    pub fn finish<T>(mut self, result: &ChannelResult<T>) {
+        if let Some(channel_id) = self.channel_id.take() {
+            self.breaker.record(&channel_id, |circuit, now_ms| {
+                circuit.finish(result, now_ms)
+            });
+        }
+    }
+}
+
+impl Drop for CircuitSend {
+    fn drop(&mut self) {
+        if let Some(channel_id) = self.channel_id.take() {
+            self.breaker
+                .record(&channel_id, |circuit, now_ms| circuit.abandon(now_ms));
+        }
+    }
+}
+
+/// Circuit breaker status of a channel, as shown by the CLI and web UI
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitStatus {
+    /// Sends since the last success that failed or were abandoned
+    pub consecutive_failures: u32,
+    /// Whether the channel is currently being skipped
+    pub open: bool,
+    /// Time the circuit closes again in milliseconds since the Unix epoch
+    pub open_until_ms: Option<i64>,
+    /// Error reported by the most recent failed send
+    pub last_error: Option<String>,
+}
+
+/// Summarize the persisted circuits of all channels that have failed recently
+pub fn circuit_status(
+    circuits: &std::collections::HashMap<String, CircuitState>,
+    settings: &CircuitBreakerConfig,
+    now_ms: i64,
+) -> BTreeMap<String, CircuitStatus> {
+    circuits
+        .iter()
+        .filter(|(_, circuit)| circuit.consecutive_failures > 0)
+        .map(|(channel_id, circuit)| {
+            let open_until_ms = circuit.open_until_ms(settings, now_ms);
+            let status = CircuitStatus {
+                consecutive_failures: circuit.consecutive_failures,
+                open: open_until_ms.is_some(),
+                open_until_ms,
+                last_error: circuit.last_error.clone(),
+            };
+            (channel_id.clone(), status)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ChannelError;
+
+    fn settings() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 2,
+            cooldown_secs: 60,
+        }
+    }
+
+    fn fail(circuit: &mut CircuitState, now_ms: i64) -> bool {
+        let started = circuit.begin(&settings(), now_ms);
+        if started {
+            circuit.finish::<()>(&Err(ChannelError::Timeout), now_ms);
+        }
+        started
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let mut circuit = CircuitState::default();
+
+        assert!(fail(&mut circuit, 1_000));
+        assert!(fail(&mut circuit, 2_000));
+        assert_eq!(circuit.open_until_ms(&settings(), 2_000), Some(62_000));
+        assert!(!fail(&mut circuit, 3_000));
+        assert_eq!(
+            circuit.last_error.as_deref(),
+            Some("Channel operation timeout")
+        );
+    }
+
+    #[test]
+    fn test_probe_after_cooldown() {
+        let mut circuit = CircuitState::default();
+        fail(&mut circuit, 1_000);
+        fail(&mut circuit, 1_000);
+
+        // A failed probe keeps the circuit open for another cooldown
+        assert!(fail(&mut circuit, 61_000));
+        assert!(!circuit.begin(&settings(), 62_000));
+
+        // A successful probe closes it
+        assert!(circuit.begin(&settings(), 121_000));
+        circuit.finish(&Ok(()), 121_500);
+        assert_eq!(circuit.consecutive_failures, 0);
+        assert_eq!(circuit.open_until_ms(&settings(), 121_000), None);
+    }
+
+    #[test]
+    fn test_concurrent_sends_in_flight() {
+        let mut circuit = CircuitState::default();
+
+        // Slow but healthy sends are not failures while they run
+        assert!(circuit.begin(&settings(), 1_000));
+        assert!(circuit.begin(&settings(), 1_000));
+        assert!(circuit.begin(&settings(), 1_000));
+        assert_eq!(circuit.in_flight, 3);
+        assert_eq!(circuit.consecutive_failures, 0);
+        assert!(circuit.begin(&settings(), 2_000));
+
+        for _ in 0..4 {
+            circuit.finish(&Ok(()), 5_000);
+        }
+        assert_eq!(circuit.in_flight, 0);
+        assert_eq!(circuit.open_until_ms(&settings(), 5_000), None);
+    }
+
+    #[test]
+    fn test_abandoned_send_counts_as_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = StateStore::new(dir.path().join("state.json"));
+        let breaker = CircuitBreaker::new(store.clone(), settings());
+
+        // Sends cut off by the hook budget are dropped without finishing
+        let first = breaker.begin("dingtalk").unwrap();
+        let second = breaker.begin("dingtalk").unwrap();
+        assert_eq!(store.load().unwrap().circuits["dingtalk"].in_flight, 2);
+        drop(first);
+        drop(second);
+
+        let state = store.load().unwrap();
+        assert_eq!(state.circuits["dingtalk"].consecutive_failures, 2);
+        assert_eq!(state.circuits["dingtalk"].in_flight, 0);
+        assert_eq!(
+            state.circuits["dingtalk"].last_error.as_deref(),
+            Some(ABANDONED_ERROR)
+        );
+        assert!(breaker.begin("dingtalk").is_none());
+    }
+
+    #[test]
+    fn test_breaker_persists_and_clears_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = StateStore::new(dir.path().join("state.json"));
+        let breaker = CircuitBreaker::new(store.clone(), settings());
+
+        let send = breaker.begin("dingtalk").unwrap();
+        send.finish::<()>(&Err(ChannelError::Timeout));
+        let send = breaker.begin("dingtalk").unwrap();
+        send.finish::<()>(&Err(ChannelError::Timeout));
+        assert!(breaker.begin("dingtalk").is_none());
+
+        let state = store.load().unwrap();
+        let status = circuit_status(
+            &state.circuits,
+            &settings(),
+            chrono::Utc::now().timestamp_millis(),
+        );
+        assert!(status["dingtalk"].open);
+        assert_eq!(status["dingtalk"].consecutive_failures, 2);
+
+        // A successful probe removes the channel from the state
+        let probe = CircuitBreaker::new(
+            store.clone(),
+            CircuitBreakerConfig {
+                cooldown_secs: 0,
+                ..settings()
+            },
+        );
+        probe.begin("dingtalk").unwrap().finish(&Ok(()));
+        assert!(store.load().unwrap().circuits.is_empty());
+    }
+}
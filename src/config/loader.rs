@@ -5,6 +5,7 @@
 
 use crate::config::schema::AppConfig;
 use crate::error::{NotificationError, Result};
+use crate::schedule::Schedule;
 use dirs::home_dir;
 use std::fs;
 use std::path::PathBuf;
@@ -17,6 +18,14 @@ pub fn get_config_path() -> PathBuf {
         .join(".claude-code-notifications.json")
 }
 
+/// Get the directory used for runtime state (worker logs, sockets, state files)
+/// Returns ~/.claude-code-notifications/
+pub fn get_state_dir() -> PathBuf {
+    home_dir()
+        .expect("Unable to determine home directory")
+        .join(".claude-code-notifications")
+}
+
 /// Load configuration from the default path
 /// If the file doesn't exist, returns a default configuration
 pub fn load_config() -> Result<AppConfig> {
@@ -36,10 +45,42 @@ pub fn load_config_from_path(path: &PathBuf) -> Result<AppConfig> {
     let config: AppConfig = serde_json::from_str(&content).map_err(|e| {
         NotificationError::ConfigError(format!("Failed to parse config JSON: {}", e))
     })?;
+    validate_config(&config)?;
 
     Ok(config)
 }
 
+/// Check settings that parse as JSON but cannot be used
+///
+/// Channel schedules are parsed here, so a typo in a day, time range or
+/// timezone is reported once when the configuration is loaded or saved
+/// instead of being ignored on every hook.
+pub fn validate_config(config: &AppConfig) -> Result<()> {
+    let mut channel_ids: Vec<&String> = config.channels.keys().collect();
+    channel_ids.sort();
+
+    let problems: Vec<String> = channel_ids
+        .into_iter()
+        .filter_map(|channel_id| {
+            let schedule = config.channels[channel_id].schedule.as_ref()?;
+            let message = match Schedule::parse(schedule).err()? {
+                NotificationError::ConfigError(message) => message,
+                e => e.to_string(),
+            };
+            Some(format!(
+                "Invalid schedule for channel '{}': {}",
+                channel_id, message
+            ))
+        })
+        .collect();
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(NotificationError::ConfigError(problems.join("\n")))
+    }
+}
+
 /// Save configuration to the default path
 pub fn save_config(config: &AppConfig) -> Result<()> {
     save_config_to_path(config, &get_config_path())
@@ -94,6 +135,7 @@ pub fn default_config() -> AppConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ScheduleConfig;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -121,6 +163,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_invalid_channel_schedule() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_path_buf();
+
+        let mut config = default_config();
+        config.channels.get_mut("system").unwrap().schedule = Some(ScheduleConfig {
+            days: vec!["Funday".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            validate_config(&config).unwrap_err().to_string(),
+            "Configuration error: Invalid schedule for channel 'system': Invalid day: Funday"
+        );
+
+        save_config_to_path(&config, &path).unwrap();
+        assert!(load_config_from_path(&path).is_err());
+    }
+
     #[test]
     fn test_load_nonexistent_config_returns_default() {
         let temp_file = NamedTempFile::new().unwrap();
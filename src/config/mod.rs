@@ -8,8 +8,12 @@ pub mod schema;
 pub mod templates;
 
 pub use loader::{
-    default_config, get_config_path, load_config, load_config_from_path, save_config,
-    save_config_to_path,
+    default_config, get_config_path, get_state_dir, load_config, load_config_from_path,
+    save_config, save_config_to_path, validate_config,
+};
+pub use schema::{
+    AppConfig, BackgroundConfig, ChannelConfig, CircuitBreakerConfig, DedupConfig, DedupKey,
+    DeliveryMode, EscalationStep, HistoryConfig, MessageTemplate, OutsideHoursPolicy,
+    OverflowPolicy, RateLimitConfig, RoutingRule, RuleMatch, ScheduleConfig, ToolInputMatch,
 };
-pub use schema::{AppConfig, ChannelConfig, MessageTemplate, RoutingRule, RuleMatch};
 pub use templates::{RenderedMessage, TemplateEngine};
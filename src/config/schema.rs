@@ -3,6 +3,7 @@
 //! This module defines the data structures for the application configuration,
 //! including channels, routing rules, and message templates.
 
+use crate::severity::Severity;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -31,12 +32,148 @@ pub struct AppConfig {
     /// Enable debug logging
     #[serde(default)]
     pub debug: bool,
+
+    /// Detached background delivery settings
+    #[serde(default)]
+    pub background: BackgroundConfig,
+
+    /// Suppression of repeated notifications
+    #[serde(default)]
+    pub dedup: DedupConfig,
+
+    /// Skipping of channels that keep failing
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+
+    /// Log of delivery attempts
+    #[serde(default)]
+    pub history: HistoryConfig,
+
+    /// Time the hook waits for all channels before giving up (milliseconds)
+    #[serde(default = "default_dispatch_budget_ms")]
+    pub dispatch_budget_ms: u64,
 }
 
 fn default_channels() -> Vec<String> {
     vec!["system".to_string()]
 }
 
+fn default_dispatch_budget_ms() -> u64 {
+    2000
+}
+
+/// Settings for detached background delivery
+///
+/// When enabled, the hook process hands the input to a detached worker
+/// process and exits immediately, so Claude Code is never blocked on
+/// slow webhooks.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BackgroundConfig {
+    /// Hand hook input to a detached worker instead of delivering in-process
+    pub enabled: bool,
+
+    /// Number of retries for channels that failed on the first attempt
+    pub max_retries: u32,
+
+    /// Delay between retry attempts in milliseconds
+    pub retry_delay_ms: u64,
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_retries: 2,
+            retry_delay_ms: 1000,
+        }
+    }
+}
+
+/// Settings for suppressing repeated notifications
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DedupConfig {
+    /// Suppress identical notifications inside the window
+    pub enabled: bool,
+
+    /// Window length in seconds, measured from the first occurrence
+    pub window_secs: u64,
+
+    /// Fields that make two notifications identical
+    pub keys: Vec<DedupKey>,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: 300,
+            keys: vec![DedupKey::SessionId, DedupKey::HookType, DedupKey::BodyHash],
+        }
+    }
+}
+
+/// Settings for skipping channels that keep failing
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// Skip channels whose circuit is open
+    pub enabled: bool,
+
+    /// Consecutive failures that open a channel's circuit
+    pub failure_threshold: u32,
+
+    /// Seconds an open circuit skips the channel before a probe send
+    pub cooldown_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            failure_threshold: 3,
+            cooldown_secs: 300,
+        }
+    }
+}
+
+/// Settings for the delivery history log
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Record every delivery attempt
+    pub enabled: bool,
+
+    /// Size in KiB at which the log is rotated; one rotated file is kept
+    pub max_file_kb: u64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_file_kb: 10 * 1024,
+        }
+    }
+}
+
+/// Hook input field used to build a deduplication key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupKey {
+    /// Claude session identifier
+    SessionId,
+    /// Hook event name
+    HookType,
+    /// Hash of the message rendered from the global template
+    BodyHash,
+    /// Tool name (PreToolUse only)
+    ToolName,
+    /// Working directory
+    Cwd,
+}
+
 /// Configuration for a specific notification channel
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
@@ -71,11 +208,151 @@ pub struct ChannelConfig {
 
     pub timeout_ms: Option<u64>,
 
+    /// Time allowed to establish a webhook connection (milliseconds)
+    pub connect_timeout_ms: Option<u64>,
+
+    /// Time allowed for a whole webhook request, including the response (milliseconds)
+    pub request_timeout_ms: Option<u64>,
+
+    /// HTTP, HTTPS or SOCKS5 proxy URL for webhook requests
+    /// (defaults to the HTTPS_PROXY/HTTP_PROXY/ALL_PROXY environment variables)
+    pub proxy: Option<String>,
+
+    /// Hosts that bypass the proxy (defaults to the NO_PROXY environment variable)
+    pub no_proxy: Vec<String>,
+
+    /// PEM file with additional CA certificates to trust
+    pub ca_bundle: Option<String>,
+
+    /// PEM client certificate for mutual TLS
+    pub client_cert: Option<String>,
+
+    /// PEM (PKCS#8) private key for the client certificate
+    /// (defaults to `client_cert`, for a combined PEM file)
+    pub client_key: Option<String>,
+
+    /// Accept invalid TLS certificates (for testing only)
+    pub insecure_skip_verify: bool,
+
+    /// Token-bucket rate limit for this channel (e.g., DingTalk's 20 msgs/min)
+    pub rate_limit: Option<RateLimitConfig>,
+
+    /// Active hours for this channel (quiet hours outside the schedule)
+    pub schedule: Option<ScheduleConfig>,
+
+    /// Send each notification immediately or batch them into periodic digests
+    pub delivery: DeliveryMode,
+
+    /// Minutes between digests in `digest` delivery mode (default: 30)
+    pub digest_interval_minutes: Option<u64>,
+
+    /// Channels that receive the same message when this channel fails
+    pub fallback: Vec<String>,
+
+    /// Mention everyone in the group on critical notifications
+    /// (DingTalk, WeChat Work and Feishu)
+    pub mention_all_on_critical: bool,
+
     /// Additional channel-specific settings
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+impl ChannelConfig {
+    /// Interval between digests in milliseconds
+    pub fn digest_interval_ms(&self) -> i64 {
+        self.digest_interval_minutes.unwrap_or(30) as i64 * 60_000
+    }
+
+    /// Whether a notification of this severity should mention everyone
+    pub fn mentions_all(&self, severity: Option<Severity>) -> bool {
+        self.mention_all_on_critical && severity == Some(Severity::Critical)
+    }
+}
+
+/// How a channel delivers notifications
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryMode {
+    /// Send each notification as it arrives
+    #[default]
+    Immediate,
+    /// Accumulate notifications and send one summary per interval or session end
+    Digest,
+}
+
+/// Token-bucket rate limit for a channel
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RateLimitConfig {
+    /// Sustained number of notifications allowed per minute
+    pub max_per_minute: u32,
+
+    /// Maximum burst size (defaults to `max_per_minute`)
+    #[serde(default)]
+    pub burst: Option<u32>,
+
+    /// What to do with notifications that exceed the limit
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+
+    /// Longest delay accepted by the `delay` policy before dropping (milliseconds)
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl RateLimitConfig {
+    /// Bucket capacity in tokens
+    pub fn capacity(&self) -> f64 {
+        self.burst.unwrap_or(self.max_per_minute) as f64
+    }
+}
+
+fn default_max_delay_ms() -> u64 {
+    60_000
+}
+
+/// Policy for notifications that exceed a channel's rate limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Wait until a token is available (up to `max_delay_ms`)
+    Delay,
+    /// Discard the notification
+    Drop,
+    /// Discard the notification and report the count with the next one
+    #[default]
+    Collapse,
+}
+
+/// Active-hours schedule for a channel or routing rule
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// Active days ("mon".."sun", "weekdays", "weekends"); empty means every day
+    pub days: Vec<String>,
+
+    /// Active time ranges such as "09:00-18:00"; a range may wrap past midnight.
+    /// Empty means all day
+    pub hours: Vec<String>,
+
+    /// IANA timezone name (e.g., "Asia/Shanghai"); defaults to local time
+    pub timezone: Option<String>,
+
+    /// What to do with notifications outside active hours
+    pub outside_hours: OutsideHoursPolicy,
+}
+
+/// Policy for notifications that arrive outside a schedule's active hours
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutsideHoursPolicy {
+    /// Discard the notification
+    #[default]
+    Skip,
+    /// Queue the notification and deliver it as a digest when active again
+    Defer,
+}
+
 /// Message template with variable substitution
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
@@ -106,11 +383,76 @@ pub struct RoutingRule {
     pub match_conditions: RuleMatch,
 
     /// Channels to use when this rule matches
+    #[serde(default)]
     pub channels: Vec<String>,
 
+    /// Channels removed from the result when this rule matches, whichever
+    /// rules (or default channels) added them
+    #[serde(default)]
+    pub exclude_channels: Vec<String>,
+
+    /// Rules are evaluated from highest to lowest priority, in file order
+    /// when equal
+    #[serde(default)]
+    pub priority: i32,
+
+    /// Stop evaluating further rules once this rule matches
+    #[serde(default)]
+    pub stop: bool,
+
+    /// Only evaluate this rule when no other rule matched
+    #[serde(default)]
+    pub default: bool,
+
     /// Whether this rule is enabled
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+
+    /// Active hours for this rule's channels
+    #[serde(default)]
+    pub schedule: Option<ScheduleConfig>,
+
+    /// Additional channels to notify while the session stays idle
+    #[serde(default)]
+    pub escalation: Vec<EscalationStep>,
+
+    /// Channels that receive the message when one of this rule's channels fails
+    #[serde(default)]
+    pub fallback: Vec<String>,
+
+    /// Severity the notification gets when this rule matches, replacing the
+    /// default for later rules and for delivery
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+}
+
+impl Default for RoutingRule {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            match_conditions: RuleMatch::default(),
+            channels: Vec::new(),
+            exclude_channels: Vec::new(),
+            priority: 0,
+            stop: false,
+            default: false,
+            enabled: default_enabled(),
+            schedule: None,
+            escalation: Vec::new(),
+            fallback: Vec::new(),
+            severity: None,
+        }
+    }
+}
+
+/// Escalation step: notify more channels if nobody responds in time
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EscalationStep {
+    /// Minutes after the original notification
+    pub after_minutes: u64,
+
+    /// Channels to notify
+    pub channels: Vec<String>,
 }
 
 fn default_enabled() -> bool {
@@ -130,6 +472,69 @@ pub struct RuleMatch {
 
     /// Regex pattern to match against tool name (PreToolUse only)
     pub tool_pattern: Option<String>,
+
+    /// Conditions on fields of the tool input, all of which must hold
+    /// (PreToolUse only)
+    pub tool_input: Vec<ToolInputMatch>,
+
+    /// Transcript statuses to match, e.g. "Question" or "APIError"
+    /// (Stop and SubagentStop only)
+    pub status: Vec<String>,
+
+    /// Lowest severity to match, as set by the defaults and by the `severity`
+    /// of rules evaluated earlier
+    pub min_severity: Option<Severity>,
+
+    /// Permission modes to match, e.g. "bypassPermissions" or "plan"
+    pub permission_modes: Vec<String>,
+
+    /// Regex pattern to match against the session id
+    pub session_pattern: Option<String>,
+
+    /// Glob (or `re:` regex) matched against this machine's hostname
+    pub hostname: Option<String>,
+
+    /// Environment variables of the notifying process: each value is a glob
+    /// (or `re:` regex) the variable must match, or `null` if it must be unset
+    pub env: HashMap<String, Option<String>>,
+
+    /// Time ranges such as "09:00-18:00" when the rule matches; a range may
+    /// wrap past midnight. Empty means all day
+    pub time_window: Vec<String>,
+
+    /// Days when the rule matches ("mon".."sun", "weekdays", "weekends");
+    /// empty means every day
+    pub weekdays: Vec<String>,
+
+    /// IANA timezone for `time_window` and `weekdays`; defaults to local time
+    pub timezone: Option<String>,
+
+    /// Glob (or `re:` regex) matched against the hook's working directory
+    pub cwd_pattern: Option<String>,
+
+    /// Glob (or `re:` regex) matched against the project name: the git
+    /// repository containing the working directory, or its directory name
+    pub project: Option<String>,
+
+    /// Boolean expression over the hook's fields, e.g.
+    /// `hook == "PreToolUse" and tool_input.command =~ "rm -rf"`
+    pub expression: Option<String>,
+}
+
+/// Condition on a field of a PreToolUse hook's tool input
+///
+/// Set exactly one of `regex` or `glob`. The condition holds when any value
+/// selected by `path` matches; non-string values are matched as JSON.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ToolInputMatch {
+    /// JSONPath selector, e.g. `$.command` or `$.edits[*].file_path`
+    pub path: String,
+
+    /// Regex matched anywhere in the selected value
+    pub regex: Option<String>,
+
+    /// Glob matched against the whole selected value, e.g. `**/prod/**`
+    pub glob: Option<String>,
 }
 
 #[cfg(test)]
@@ -149,6 +554,10 @@ mod tests {
         assert_eq!(config.default_channels, vec!["system"]);
         assert!(config.channels.is_empty());
         assert!(config.routing_rules.is_empty());
+        assert!(!config.background.enabled);
+        assert_eq!(config.background.max_retries, 2);
+        assert!(!config.dedup.enabled);
+        assert_eq!(config.dedup.keys.len(), 3);
     }
 
     #[test]
@@ -171,6 +580,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_channel_config_with_rate_limit() {
+        let config: ChannelConfig = serde_json::from_value(json!({
+            "enabled": true,
+            "rate_limit": {
+                "max_per_minute": 20,
+                "overflow": "delay"
+            }
+        }))
+        .unwrap();
+
+        let limit = config.rate_limit.unwrap();
+        assert_eq!(limit.max_per_minute, 20);
+        assert_eq!(limit.capacity(), 20.0);
+        assert_eq!(limit.overflow, OverflowPolicy::Delay);
+        assert_eq!(limit.max_delay_ms, 60_000);
+    }
+
+    #[test]
+    fn test_channel_config_with_digest() {
+        let config: ChannelConfig = serde_json::from_value(json!({
+            "enabled": true,
+            "delivery": "digest",
+            "digest_interval_minutes": 15
+        }))
+        .unwrap();
+
+        assert_eq!(config.delivery, DeliveryMode::Digest);
+        assert_eq!(config.digest_interval_ms(), 15 * 60_000);
+        assert!(config.fallback.is_empty());
+        assert_eq!(ChannelConfig::default().delivery, DeliveryMode::Immediate);
+        assert_eq!(ChannelConfig::default().digest_interval_ms(), 30 * 60_000);
+    }
+
+    #[test]
+    fn test_routing_rule_with_escalation() {
+        let rule: RoutingRule = serde_json::from_value(json!({
+            "name": "Permissions",
+            "match": { "hook_types": ["PermissionRequest"] },
+            "channels": ["system"],
+            "escalation": [
+                { "after_minutes": 5, "channels": ["dingtalk-personal"] },
+                { "after_minutes": 15, "channels": ["dingtalk-team"] }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(rule.escalation.len(), 2);
+        assert_eq!(rule.escalation[1].after_minutes, 15);
+        assert_eq!(rule.escalation[1].channels, vec!["dingtalk-team"]);
+    }
+
     #[test]
     fn test_routing_rule() {
         let rule: RoutingRule = serde_json::from_value(json!({
@@ -5,6 +5,7 @@
 
 use crate::config::schema::MessageTemplate;
 use crate::hooks::{HookData, HookInput, HookType};
+use serde::Serialize;
 use std::collections::HashMap;
 
 /// Template engine for rendering messages
@@ -71,6 +72,10 @@ impl TemplateEngine {
         let mut ctx = HashMap::new();
         ctx.insert("hook_type".to_string(), format!("{:?}", input.hook_event_name));
         ctx.insert("session_id".to_string(), input.common.session_id.clone());
+        ctx.insert(
+            "severity".to_string(),
+            input.severity.unwrap_or_default().to_string(),
+        );
 
         if let Some(transcript_path) = &input.common.transcript_path {
             ctx.insert("transcript_path".to_string(), transcript_path.clone());
@@ -114,11 +119,11 @@ impl TemplateEngine {
                 // For Stop hooks, try to analyze transcript and generate message
                 // If no transcript available or analysis fails, use default
                 let message = if let Some(transcript_path) = &input.common.transcript_path {
-                    match crate::analyzer::analyze_transcript(transcript_path) {
-                        Ok(status) => {
+                    match input.transcript_status() {
+                        Some(status) => {
                             crate::summary::generate_summary(transcript_path, status)
                         }
-                        Err(_) => "Claude stopped generating".to_string()
+                        None => "Claude stopped generating".to_string()
                     }
                 } else {
                     "Claude stopped generating".to_string()
@@ -130,11 +135,11 @@ impl TemplateEngine {
             HookData::SubagentStop(_data) => {
                 // For SubagentStop hooks, try to analyze transcript and generate message
                 let message = if let Some(transcript_path) = &input.common.transcript_path {
-                    match crate::analyzer::analyze_transcript(transcript_path) {
-                        Ok(status) => {
+                    match input.transcript_status() {
+                        Some(status) => {
                             crate::summary::generate_summary(transcript_path, status)
                         }
-                        Err(_) => "Subagent stopped".to_string()
+                        None => "Subagent stopped".to_string()
                     }
                 } else {
                     "Subagent stopped".to_string()
@@ -155,6 +160,13 @@ impl TemplateEngine {
                     ctx.insert("context".to_string(), context.clone());
                 }
             }
+            HookData::SessionEnd(data) => {
+                ctx.insert(
+                    "message".to_string(),
+                    format!("Session ended ({})", data.reason),
+                );
+                ctx.insert("reason".to_string(), data.reason.clone());
+            }
         }
 
         ctx
@@ -174,7 +186,7 @@ impl TemplateEngine {
 }
 
 /// Rendered message with title and body
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RenderedMessage {
     pub title: String,
     pub body: String,
@@ -214,6 +226,19 @@ mod tests {
         let rendered = engine.render(&template, &input);
         assert_eq!(rendered.title, "Notification - Test Title");
         assert_eq!(rendered.body, "Test message");
+
+        let template = MessageTemplate {
+            title: Some("[{{severity}}] {{title}}".to_string()),
+            ..Default::default()
+        };
+        let input = HookInput {
+            severity: Some(crate::severity::Severity::Warning),
+            ..input
+        };
+        assert_eq!(
+            engine.render(&template, &input).title,
+            "[warning] Test Title"
+        );
     }
 
     #[test]
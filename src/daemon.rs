@@ -0,0 +1,337 @@
+//! Long-running notification daemon
+//!
+//! The daemon keeps the parsed configuration and a `ChannelManager` in memory
+//! and accepts hook events over a Unix domain socket. Short-lived hook
+//! invocations forward their input to the daemon when it is running and fall
+//! back to in-process delivery otherwise.
+//!
+//! The wire protocol is one JSON-encoded [`DaemonRequest`] per line. The daemon
+//! replies with a single `ok` line as soon as the request is parsed, or
+//! `error` if it cannot be, then completes delivery asynchronously.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::get_state_dir;
+use crate::error::Result;
+use crate::hooks::HookInput;
+
+#[cfg(unix)]
+use crate::ChannelManager;
+#[cfg(unix)]
+use std::sync::Arc;
+#[cfg(unix)]
+use std::time::{Duration, SystemTime};
+
+/// How often the daemon flushes the outbox and fires due escalations
+#[cfg(unix)]
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Request sent from a hook invocation to the daemon
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DaemonRequest {
+    /// Hook input to deliver
+    pub input: HookInput,
+    /// Explicit channels (bypasses routing rules when set)
+    #[serde(default)]
+    pub channels: Option<Vec<String>>,
+    /// Environment variables read by routing rules, as the hook saw them
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// Get the path of the daemon's Unix socket
+/// Returns ~/.claude-code-notifications/daemon.sock
+pub fn get_socket_path() -> PathBuf {
+    get_state_dir().join("daemon.sock")
+}
+
+/// Forward a hook input to the running daemon
+///
+/// `env` holds the variables routing rules read (see
+/// [`ChannelRouter::captured_env`](crate::ChannelRouter::captured_env)); the
+/// daemon routes with them instead of its own environment.
+///
+/// Returns `Ok(true)` once the request is written to the daemon and
+/// `Ok(false)` when no daemon is listening or it rejected the request, so
+/// the caller can fall back to in-process delivery. A missing or late reply
+/// after a successful write still counts as handed off: the daemon already
+/// has the event, and delivering it in-process as well would duplicate it.
+pub fn forward_to_daemon(
+    input: &HookInput,
+    channels: Option<&[String]>,
+    env: HashMap<String, String>,
+) -> Result<bool> {
+    forward_to_socket(&get_socket_path(), input, channels, env)
+}
+
+#[cfg(unix)]
+fn forward_to_socket(
+    socket_path: &Path,
+    input: &HookInput,
+    channels: Option<&[String]>,
+    env: HashMap<String, String>,
+) -> Result<bool> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    if !socket_path.exists() {
+        return Ok(false);
+    }
+
+    let mut stream = match UnixStream::connect(socket_path) {
+        Ok(stream) => stream,
+        Err(e) => {
+            crate::debug_context!("daemon", "Daemon not reachable: {}", e);
+            return Ok(false);
+        }
+    };
+    stream.set_write_timeout(Some(Duration::from_millis(500)))?;
+    stream.set_read_timeout(Some(Duration::from_millis(500)))?;
+
+    let request = DaemonRequest {
+        input: input.clone(),
+        channels: channels.map(|c| c.to_vec()),
+        env,
+    };
+    let mut line = serde_json::to_vec(&request)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+
+    let mut reply = String::new();
+    if let Err(e) = BufReader::new(&stream).read_line(&mut reply) {
+        crate::debug_context!("daemon", "No reply from daemon: {}", e);
+    }
+    Ok(reply.trim() != "error")
+}
+
+#[cfg(not(unix))]
+fn forward_to_socket(
+    _socket_path: &Path,
+    _input: &HookInput,
+    _channels: Option<&[String]>,
+    _env: HashMap<String, String>,
+) -> Result<bool> {
+    Ok(false)
+}
+
+/// Run the daemon until interrupted
+///
+/// Binds the Unix socket (replacing a stale socket file left by a crashed
+/// daemon), then serves requests until Ctrl+C. The configuration is reloaded
+/// whenever the config file's modification time changes, and deferred
+/// notifications and escalations are flushed once a minute.
+#[cfg(unix)]
+pub async fn run_daemon(socket_path: &Path) -> Result<()> {
+    use crate::error::NotificationError;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixListener;
+    use tokio::sync::RwLock;
+
+    if socket_path.exists() {
+        if std::os::unix::net::UnixStream::connect(socket_path).is_ok() {
+            return Err(NotificationError::InvalidInput(format!(
+                "A daemon is already listening on {}",
+                socket_path.display()
+            )));
+        }
+        std::fs::remove_file(socket_path)?;
+    }
+    if let Some(parent) = socket_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let state = Arc::new(RwLock::new(ManagerState::load()?));
+    let listener = UnixListener::bind(socket_path)?;
+    println!("Listening on {}", socket_path.display());
+
+    let mut maintenance = tokio::time::interval(MAINTENANCE_INTERVAL);
+
+    loop {
+        let (stream, _) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = maintenance.tick() => {
+                // Deliver deferred notifications and escalations even when no hooks are firing
+                let manager = ManagerState::current(&state).await;
+                tokio::spawn(async move {
+                    if let Err(e) = manager.flush_due_async().await {
+                        eprintln!("Outbox flush error: {}", e);
+                    }
+                });
+                continue;
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        };
+
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+            let mut line = String::new();
+            if let Err(e) = BufReader::new(reader).read_line(&mut line).await {
+                eprintln!("Failed to read daemon request: {}", e);
+                return;
+            }
+
+            let request: DaemonRequest = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    eprintln!("Invalid daemon request: {}", e);
+                    let _ = writer.write_all(b"error\n").await;
+                    return;
+                }
+            };
+            // Acknowledge before delivering so the hook process can exit
+            let _ = writer.write_all(b"ok\n").await;
+
+            let manager = ManagerState::current(&state).await;
+            let input = request.input.with_env(request.env);
+            if let Err(e) = manager
+                .deliver_in_background_async(&input, request.channels)
+                .await
+            {
+                eprintln!("Delivery error: {}", e);
+            }
+        });
+    }
+
+    let _ = std::fs::remove_file(socket_path);
+    println!("Daemon stopped");
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn run_daemon(_socket_path: &Path) -> Result<()> {
+    Err(crate::error::NotificationError::InvalidInput(
+        "The daemon requires Unix domain sockets and is not supported on this platform".to_string(),
+    ))
+}
+
+/// In-memory channel manager with the config mtime it was loaded from
+#[cfg(unix)]
+struct ManagerState {
+    manager: Arc<ChannelManager>,
+    modified: Option<SystemTime>,
+}
+
+#[cfg(unix)]
+impl ManagerState {
+    fn load() -> Result<Self> {
+        Ok(Self {
+            // The maintenance tick replaces per-escalation timer processes
+            manager: Arc::new(ChannelManager::load()?.with_flush_timers(false)),
+            modified: config_modified(),
+        })
+    }
+
+    /// Get the current manager, reloading it first if the config file changed
+    async fn current(state: &tokio::sync::RwLock<Self>) -> Arc<ChannelManager> {
+        let modified = config_modified();
+        {
+            let current = state.read().await;
+            if current.modified == modified {
+                return Arc::clone(&current.manager);
+            }
+        }
+
+        let mut current = state.write().await;
+        match Self::load() {
+            Ok(reloaded) => {
+                crate::debug_context!("daemon", "Configuration reloaded");
+                *current = reloaded;
+            }
+            Err(e) => {
+                // Keep serving with the previous configuration
+                eprintln!("Failed to reload configuration: {}", e);
+                current.modified = modified;
+            }
+        }
+        Arc::clone(&current.manager)
+    }
+}
+
+/// Modification time of the config file, if it exists
+#[cfg(unix)]
+fn config_modified() -> Option<SystemTime> {
+    std::fs::metadata(crate::config::get_config_path())
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daemon_request_roundtrip() {
+        let request = DaemonRequest {
+            input: HookInput::notification(
+                "test-session".to_string(),
+                None,
+                "Hello daemon".to_string(),
+                None,
+            ),
+            channels: Some(vec!["system".to_string()]),
+            env: HashMap::from([("CI".to_string(), "true".to_string())]),
+        };
+
+        let json = serde_json::to_string(&request).unwrap();
+        let parsed: DaemonRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.input.common.session_id, "test-session");
+        assert_eq!(parsed.channels, Some(vec!["system".to_string()]));
+        assert_eq!(parsed.env, request.env);
+
+        // Requests from older hook binaries carry no environment
+        let parsed: DaemonRequest = serde_json::from_str(&format!(
+            r#"{{"input": {}}}"#,
+            serde_json::to_string(&request.input).unwrap()
+        ))
+        .unwrap();
+        assert!(parsed.env.is_empty());
+    }
+
+    #[test]
+    fn test_forward_without_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = HookInput::notification("test".to_string(), None, "Test".to_string(), None);
+
+        let forwarded = forward_to_socket(
+            &dir.path().join("missing.sock"),
+            &input,
+            None,
+            HashMap::new(),
+        )
+        .unwrap();
+        assert!(!forwarded);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_forward_without_reply_counts_as_handed_off() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("daemon.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        let input = HookInput::notification("test".to_string(), None, "Test".to_string(), None);
+
+        // The daemon reads the request and hangs up without replying
+        let server = std::thread::spawn(move || {
+            for reply in [None, Some("error\n")] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut line = String::new();
+                BufReader::new(&stream).read_line(&mut line).unwrap();
+                if let Some(reply) = reply {
+                    stream.write_all(reply.as_bytes()).unwrap();
+                }
+            }
+        });
+
+        assert!(forward_to_socket(&socket_path, &input, None, HashMap::new()).unwrap());
+        // A request the daemon rejects is delivered in-process instead
+        assert!(!forward_to_socket(&socket_path, &input, None, HashMap::new()).unwrap());
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+//! Deduplication of repeated notifications
+//!
+//! Claude Code often repeats the same hook event (for example the idle_prompt
+//! Notification, or back-to-back PreToolUse events for AskUserQuestion). This
+//! module builds a key from the configured fields of a hook input and
+//! suppresses notifications whose key was already seen inside the window.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::config::{DedupKey, TemplateEngine};
+use crate::hooks::{HookData, HookInput};
+
+/// Build the deduplication key for a hook input
+///
+/// The key is a hash of the selected fields. `body_hash` uses the message
+/// rendered from the global template for the hook type, so notifications that
+/// would look identical to the user share a key.
+pub fn dedup_key(input: &HookInput, keys: &[DedupKey], template_engine: &TemplateEngine) -> String {
+    let mut hasher = Sha256::new();
+
+    for key in keys {
+        let part = match key {
+            DedupKey::SessionId => input.common.session_id.clone(),
+            DedupKey::HookType => format!("{:?}", input.hook_event_name),
+            DedupKey::Cwd => input.common.cwd.clone().unwrap_or_default(),
+            DedupKey::ToolName => match &input.data {
+                HookData::PreToolUse(data) => data.tool_name.clone(),
+                _ => String::new(),
+            },
+            DedupKey::BodyHash => {
+                let template = template_engine.get_template(&input.hook_event_name, None);
+                let rendered = template_engine.render(&template, input);
+                format!("{}\n{}", rendered.title, rendered.body)
+            }
+        };
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+
+    hex::encode(&hasher.finalize()[..16])
+}
+
+/// Check whether a key was seen inside the window and record it if not
+///
+/// Returns `true` when the notification is a duplicate. Expired entries are
+/// pruned so the persisted map stays small. The window is fixed from the first
+/// occurrence, so a notification that keeps repeating is still delivered once
+/// per window.
+pub fn check_and_record(
+    seen: &mut HashMap<String, i64>,
+    key: &str,
+    now_ms: i64,
+    window_ms: i64,
+) -> bool {
+    seen.retain(|_, first_seen| now_ms - *first_seen < window_ms);
+
+    if seen.contains_key(key) {
+        return true;
+    }
+
+    seen.insert(key.to_string(), now_ms);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_keys() -> Vec<DedupKey> {
+        vec![DedupKey::SessionId, DedupKey::HookType, DedupKey::BodyHash]
+    }
+
+    #[test]
+    fn test_identical_inputs_share_key() {
+        let engine = TemplateEngine::new(HashMap::new());
+        let a = HookInput::notification("s1".to_string(), None, "Idle".to_string(), None);
+        let b = HookInput::notification("s1".to_string(), None, "Idle".to_string(), None);
+
+        assert_eq!(
+            dedup_key(&a, &all_keys(), &engine),
+            dedup_key(&b, &all_keys(), &engine)
+        );
+    }
+
+    #[test]
+    fn test_different_sessions_differ() {
+        let engine = TemplateEngine::new(HashMap::new());
+        let a = HookInput::notification("s1".to_string(), None, "Idle".to_string(), None);
+        let b = HookInput::notification("s2".to_string(), None, "Idle".to_string(), None);
+
+        assert_ne!(
+            dedup_key(&a, &all_keys(), &engine),
+            dedup_key(&b, &all_keys(), &engine)
+        );
+        // Without session_id in the key, they are duplicates
+        let keys = vec![DedupKey::HookType];
+        assert_eq!(dedup_key(&a, &keys, &engine), dedup_key(&b, &keys, &engine));
+    }
+
+    #[test]
+    fn test_window_expiry() {
+        let mut seen = HashMap::new();
+
+        assert!(!check_and_record(&mut seen, "k", 1_000, 60_000));
+        assert!(check_and_record(&mut seen, "k", 30_000, 60_000));
+        assert!(check_and_record(&mut seen, "k", 60_999, 60_000));
+        assert!(!check_and_record(&mut seen, "k", 61_000, 60_000));
+    }
+}
@@ -86,6 +86,43 @@ pub enum ChannelError {
 
     #[error("Channel operation timeout")]
     Timeout,
+
+    #[error("Webhook signature rejected: {0}")]
+    SignatureInvalid(String),
+
+    #[error("Rate limited by webhook: {0}")]
+    RateLimited(String),
+
+    #[error("Message blocked by webhook keyword filter: {0}")]
+    KeywordBlocked(String),
+
+    #[error("Webhook token invalid or revoked: {0}")]
+    TokenRevoked(String),
+
+    #[error("Webhook error code {code}: {message}")]
+    VendorError { code: i64, message: String },
+
+    #[error("Circuit open after repeated failures")]
+    CircuitOpen,
+}
+
+impl ChannelError {
+    /// Whether sending the same message again could succeed
+    ///
+    /// Configuration problems such as a bad signature or a revoked token fail
+    /// the same way on every attempt, so they are not worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(
+            self,
+            Self::InvalidConfig(_)
+                | Self::DisabledError
+                | Self::NotFound(_)
+                | Self::SignatureInvalid(_)
+                | Self::KeywordBlocked(_)
+                | Self::TokenRevoked(_)
+                | Self::CircuitOpen
+        )
+    }
 }
 
 /// Result type alias for the notification system
@@ -0,0 +1,208 @@
+//! Escalation chains for unanswered notifications
+//!
+//! A routing rule can list escalation steps that notify additional channels
+//! when the session stays idle, for example DingTalk after 5 minutes and the
+//! team group after 15. Pending steps are kept in the runtime state and are
+//! cancelled as soon as the session shows activity again: any later hook for
+//! it other than a reminder of the same wait, a new user entry in the
+//! transcript (a typed message or an answer to a prompt), or the session
+//! ending.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::hooks::{HookInput, HookType};
+use crate::transcript;
+
+/// An escalation step waiting for its delay to elapse
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PendingEscalation {
+    /// Routing rule that defined the step
+    pub rule: String,
+    /// Claude session identifier
+    pub session_id: String,
+    /// Channels to notify when the step fires
+    pub channels: Vec<String>,
+    /// Time the step fires (ms since epoch)
+    pub due_at_ms: i64,
+    /// Time the original notification was sent (ms since epoch)
+    pub created_at_ms: i64,
+    /// Hook input of the original notification
+    pub input: HookInput,
+}
+
+/// What a hook input shows about the user returning to its session
+#[derive(Debug, Clone)]
+pub struct UserActivity {
+    session_id: String,
+    ended: bool,
+    seen_at_ms: i64,
+    reminds: Vec<String>,
+    replied_at_ms: Option<i64>,
+}
+
+impl UserActivity {
+    /// Read the activity a hook input seen at `now` shows
+    ///
+    /// `reminds` lists the rules whose escalations the hook itself matches.
+    /// Such a hook repeats the wait those chains are about, for example a
+    /// `Notification` reminder after a `PermissionRequest`, so only the
+    /// transcript or the session ending can answer their steps.
+    pub fn of(input: &HookInput, reminds: &[String], now: DateTime<Utc>) -> Self {
+        let ended = input.hook_event_name == HookType::SessionEnd;
+        Self {
+            session_id: input.common.session_id.clone(),
+            ended,
+            seen_at_ms: now.timestamp_millis(),
+            reminds: reminds.to_vec(),
+            replied_at_ms: if ended { None } else { last_reply_ms(input) },
+        }
+    }
+
+    /// Check whether the session was active after a step was registered
+    pub fn answers(&self, step: &PendingEscalation) -> bool {
+        step.session_id == self.session_id
+            && (self.ended
+                || (self.seen_at_ms > step.created_at_ms && !self.reminds.contains(&step.rule))
+                || self
+                    .replied_at_ms
+                    .is_some_and(|replied_at_ms| replied_at_ms > step.created_at_ms))
+    }
+}
+
+/// Check whether the user wrote to or answered the session after the given time
+pub fn user_replied_since(input: &HookInput, since_ms: i64) -> bool {
+    last_reply_ms(input).is_some_and(|replied_at_ms| replied_at_ms > since_ms)
+}
+
+/// Time of the latest user entry in the input's transcript (ms since epoch)
+fn last_reply_ms(input: &HookInput) -> Option<i64> {
+    let path = input.common.transcript_path.as_ref()?;
+    let messages = transcript::parse_file(path).ok()?;
+    transcript::last_user_entry_at(&messages).map(|time| time.timestamp_millis())
+}
+
+/// Remove and return the pending escalations that are due
+pub fn take_due(
+    pending: &mut Vec<PendingEscalation>,
+    now: DateTime<Utc>,
+) -> Vec<PendingEscalation> {
+    let now_ms = now.timestamp_millis();
+    let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(pending)
+        .into_iter()
+        .partition(|step| step.due_at_ms <= now_ms);
+    *pending = waiting;
+    due
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn step(due_at_ms: i64) -> PendingEscalation {
+        PendingEscalation {
+            rule: "Permissions".to_string(),
+            session_id: "s1".to_string(),
+            channels: vec!["dingtalk".to_string()],
+            due_at_ms,
+            created_at_ms: 0,
+            input: HookInput::permission_request("s1".to_string(), None, None, None, None),
+        }
+    }
+
+    #[test]
+    fn test_user_activity() {
+        let mut step = step(5_000);
+        step.created_at_ms = 1_000;
+        let later = Utc.timestamp_millis_opt(2_000).unwrap();
+        let earlier = Utc.timestamp_millis_opt(500).unwrap();
+
+        // Any later hook for the session shows it is active again
+        let tool = HookInput::pre_tool_use("s1".to_string(), None, "Bash".to_string(), None);
+        assert!(UserActivity::of(&tool, &[], later).answers(&step));
+        assert!(!UserActivity::of(&tool, &[], earlier).answers(&step));
+        let stop = HookInput::stop("s1".to_string(), None, None);
+        assert!(UserActivity::of(&stop, &[], later).answers(&step));
+
+        // A reminder of the same wait keeps the chain running
+        let reminder = HookInput::notification("s1".to_string(), None, "Waiting".to_string(), None);
+        let reminds = ["Permissions".to_string()];
+        assert!(!UserActivity::of(&reminder, &reminds, later).answers(&step));
+
+        let end = HookInput::session_end("s1".to_string(), None, "logout".to_string());
+        assert!(UserActivity::of(&end, &reminds, earlier).answers(&step));
+        let other = HookInput::session_end("s2".to_string(), None, "logout".to_string());
+        assert!(!UserActivity::of(&other, &[], later).answers(&step));
+    }
+
+    fn transcript_with(entry: &[u8]) -> tempfile::NamedTempFile {
+        let mut transcript = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut transcript, entry).unwrap();
+        transcript
+    }
+
+    #[test]
+    fn test_user_reply_in_transcript() {
+        let transcript = transcript_with(
+            br#"{"type":"user","message":{"content":[{"type":"text","text":"yes"}]},"timestamp":"2024-01-01T00:10:00Z"}"#,
+        );
+        let path = transcript.path().to_string_lossy().into_owned();
+        let reminder =
+            HookInput::notification("s1".to_string(), Some(path), "Waiting".to_string(), None);
+        let reminds = ["Permissions".to_string()];
+        let replied_at_ms = Utc
+            .with_ymd_and_hms(2024, 1, 1, 0, 10, 0)
+            .unwrap()
+            .timestamp_millis();
+        let now = Utc.timestamp_millis_opt(replied_at_ms + 60_000).unwrap();
+
+        let mut registered = step(0);
+        registered.created_at_ms = replied_at_ms - 1;
+        assert!(UserActivity::of(&reminder, &reminds, now).answers(&registered));
+        assert!(user_replied_since(&reminder, replied_at_ms - 1));
+
+        registered.created_at_ms = replied_at_ms + 1;
+        assert!(!UserActivity::of(&reminder, &reminds, now).answers(&registered));
+    }
+
+    #[test]
+    fn test_tool_result_reply_in_transcript() {
+        // Answering a permission prompt or question records a tool result
+        let transcript = transcript_with(
+            br#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]},"timestamp":"2024-01-01T00:10:00Z"}"#,
+        );
+        let path = transcript.path().to_string_lossy().into_owned();
+        let reminder =
+            HookInput::notification("s1".to_string(), Some(path), "Waiting".to_string(), None);
+        let reminds = ["Permissions".to_string()];
+        let replied_at_ms = Utc
+            .with_ymd_and_hms(2024, 1, 1, 0, 10, 0)
+            .unwrap()
+            .timestamp_millis();
+        let now = Utc.timestamp_millis_opt(replied_at_ms + 60_000).unwrap();
+
+        let mut registered = step(0);
+        registered.created_at_ms = replied_at_ms - 1;
+        assert!(UserActivity::of(&reminder, &reminds, now).answers(&registered));
+        assert!(user_replied_since(&reminder, replied_at_ms - 1));
+    }
+
+    #[test]
+    fn test_take_due() {
+        let mut pending = vec![step(1_000), step(5_000)];
+        let now = Utc.timestamp_millis_opt(2_000).unwrap();
+
+        let due = take_due(&mut pending, now);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].due_at_ms, 1_000);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].due_at_ms, 5_000);
+    }
+
+    #[test]
+    fn test_user_reply_without_transcript() {
+        let input = HookInput::permission_request("s1".to_string(), None, None, None, None);
+        assert!(!user_replied_since(&input, 0));
+    }
+}
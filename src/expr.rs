@@ -0,0 +1,717 @@
+//! Boolean expressions for routing rule conditions
+//!
+//! A rule's `expression` is compiled once when the router is built and then
+//! evaluated against a JSON view of the hook input. The language has
+//! `and`/`or`/`not` (or `&&`, `||`, `!`), parentheses, comparisons (`==`,
+//! `!=`, `<`, `<=`, `>`, `>=`), regex matching (`=~`, `!~`) and membership
+//! (`in`) over string, number, boolean, `null` and list literals and field
+//! paths such as `tool_input.command` or `tool_input.edits[0]`:
+//!
+//! ```text
+//! hook == "PreToolUse" and tool_input.command =~ "rm -rf" or status in ["APIError", "SessionLimitReached"]
+//! ```
+//!
+//! Fields missing from the input are `null`. A field used on its own as a
+//! condition is true unless it is `null`, `false`, zero or empty.
+
+use regex::Regex;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Value of fields that are missing from the input
+static NULL: Value = Value::Null;
+
+/// Compile error, located by 1-based character column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExprError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+/// Compiled rule condition
+#[derive(Debug, Clone)]
+pub struct Expr {
+    root: Node,
+}
+
+impl Expr {
+    /// Compile an expression
+    pub fn parse(source: &str) -> Result<Self, ExprError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+        let root = parser.parse_or()?;
+
+        let next = parser.peek();
+        if next.token != Token::Eof {
+            return Err(ExprError {
+                column: next.column,
+                message: format!(
+                    "Unexpected {}, expected 'and', 'or' or the end of the expression",
+                    next.token.describe()
+                ),
+            });
+        }
+
+        Ok(Self { root })
+    }
+
+    /// Evaluate the condition against a hook input's fields
+    pub fn evaluate(&self, context: &Value) -> bool {
+        self.root.evaluate(context)
+    }
+
+    /// Check whether the expression reads a top-level field
+    ///
+    /// Lets callers skip computing fields that are expensive to provide.
+    pub fn references(&self, field: &str) -> bool {
+        self.root.references(field)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(Operand, CompareOp, Operand),
+    Match {
+        operand: Operand,
+        regex: Regex,
+        negate: bool,
+    },
+    In(Operand, Operand),
+    Truthy(Operand),
+}
+
+impl Node {
+    fn evaluate(&self, context: &Value) -> bool {
+        match self {
+            Node::Or(left, right) => left.evaluate(context) || right.evaluate(context),
+            Node::And(left, right) => left.evaluate(context) && right.evaluate(context),
+            Node::Not(node) => !node.evaluate(context),
+            Node::Compare(left, op, right) => {
+                let (left, right) = (left.resolve(context), right.resolve(context));
+                match op {
+                    CompareOp::Eq => values_equal(left, right),
+                    CompareOp::Ne => !values_equal(left, right),
+                    CompareOp::Lt => compare(left, right) == Some(Ordering::Less),
+                    CompareOp::Le => {
+                        matches!(compare(left, right), Some(Ordering::Less | Ordering::Equal))
+                    }
+                    CompareOp::Gt => compare(left, right) == Some(Ordering::Greater),
+                    CompareOp::Ge => matches!(
+                        compare(left, right),
+                        Some(Ordering::Greater | Ordering::Equal)
+                    ),
+                }
+            }
+            Node::Match {
+                operand,
+                regex,
+                negate,
+            } => {
+                let matched = match operand.resolve(context) {
+                    Value::Null => false,
+                    Value::String(s) => regex.is_match(s),
+                    other => regex.is_match(&other.to_string()),
+                };
+                matched != *negate
+            }
+            Node::In(needle, haystack) => {
+                let needle = needle.resolve(context);
+                match haystack.resolve(context) {
+                    Value::Array(items) => items.iter().any(|item| values_equal(needle, item)),
+                    Value::String(s) => needle.as_str().is_some_and(|n| s.contains(n)),
+                    Value::Object(map) => needle.as_str().is_some_and(|n| map.contains_key(n)),
+                    _ => false,
+                }
+            }
+            Node::Truthy(operand) => is_truthy(operand.resolve(context)),
+        }
+    }
+
+    fn references(&self, field: &str) -> bool {
+        match self {
+            Node::Or(left, right) | Node::And(left, right) => {
+                left.references(field) || right.references(field)
+            }
+            Node::Not(node) => node.references(field),
+            Node::Compare(left, _, right) | Node::In(left, right) => {
+                left.references(field) || right.references(field)
+            }
+            Node::Match { operand, .. } | Node::Truthy(operand) => operand.references(field),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Literal(Value),
+    Path(Vec<Segment>),
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, context: &'a Value) -> &'a Value {
+        match self {
+            Operand::Literal(value) => value,
+            Operand::Path(segments) => segments
+                .iter()
+                .try_fold(context, |value, segment| match segment {
+                    Segment::Key(key) => value.get(key),
+                    Segment::Index(index) => value.get(index),
+                })
+                .unwrap_or(&NULL),
+        }
+    }
+
+    fn references(&self, field: &str) -> bool {
+        match self {
+            Operand::Path(segments) => {
+                matches!(segments.first(), Some(Segment::Key(key)) if key == field)
+            }
+            Operand::Literal(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Compare(CompareOp),
+    Match,
+    NotMatch,
+    And,
+    Or,
+    Not,
+    In,
+    True,
+    False,
+    Null,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Dot,
+    Eof,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(name) => format!("'{}'", name),
+            Token::Str(_) => "string".to_string(),
+            Token::Num(_) => "number".to_string(),
+            Token::Compare(op) => {
+                let symbol = match op {
+                    CompareOp::Eq => "==",
+                    CompareOp::Ne => "!=",
+                    CompareOp::Lt => "<",
+                    CompareOp::Le => "<=",
+                    CompareOp::Gt => ">",
+                    CompareOp::Ge => ">=",
+                };
+                format!("'{}'", symbol)
+            }
+            Token::Match => "'=~'".to_string(),
+            Token::NotMatch => "'!~'".to_string(),
+            Token::And => "'and'".to_string(),
+            Token::Or => "'or'".to_string(),
+            Token::Not => "'not'".to_string(),
+            Token::In => "'in'".to_string(),
+            Token::True => "'true'".to_string(),
+            Token::False => "'false'".to_string(),
+            Token::Null => "'null'".to_string(),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::LBracket => "'['".to_string(),
+            Token::RBracket => "']'".to_string(),
+            Token::Comma => "','".to_string(),
+            Token::Dot => "'.'".to_string(),
+            Token::Eof => "end of expression".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Spanned {
+    token: Token,
+    column: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Spanned>, ExprError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let (token, len) = match (c, next) {
+            ('=', Some('=')) => (Token::Compare(CompareOp::Eq), 2),
+            ('=', Some('~')) => (Token::Match, 2),
+            ('!', Some('=')) => (Token::Compare(CompareOp::Ne), 2),
+            ('!', Some('~')) => (Token::NotMatch, 2),
+            ('<', Some('=')) => (Token::Compare(CompareOp::Le), 2),
+            ('>', Some('=')) => (Token::Compare(CompareOp::Ge), 2),
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('<', _) => (Token::Compare(CompareOp::Lt), 1),
+            ('>', _) => (Token::Compare(CompareOp::Gt), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('[', _) => (Token::LBracket, 1),
+            (']', _) => (Token::RBracket, 1),
+            (',', _) => (Token::Comma, 1),
+            ('.', _) => (Token::Dot, 1),
+            ('=', _) => {
+                return Err(ExprError {
+                    column,
+                    message: "Unexpected '=', use '==' to compare".to_string(),
+                })
+            }
+            ('"' | '\'', _) => {
+                let (value, len) = read_string(&chars[i..], column)?;
+                (Token::Str(value), len)
+            }
+            _ if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                let digits = |from: usize| {
+                    chars[from..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_digit())
+                        .count()
+                };
+                let mut len = 1 + digits(i + 1);
+                // A dot only continues the number when a digit follows, so
+                // `edits.0.path` keeps its field separators
+                if chars.get(i + len) == Some(&'.')
+                    && chars.get(i + len + 1).is_some_and(|c| c.is_ascii_digit())
+                {
+                    len += 1 + digits(i + len + 1);
+                }
+                let text: String = chars[i..i + len].iter().collect();
+                let value = text.parse().map_err(|_| ExprError {
+                    column,
+                    message: format!("Invalid number '{}'", text),
+                })?;
+                (Token::Num(value), len)
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_')
+                    .count();
+                let word: String = chars[i..i + len].iter().collect();
+                let token = match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "in" => Token::In,
+                    "true" => Token::True,
+                    "false" => Token::False,
+                    "null" => Token::Null,
+                    _ => Token::Ident(word),
+                };
+                (token, len)
+            }
+            _ => {
+                return Err(ExprError {
+                    column,
+                    message: format!("Unexpected character '{}'", c),
+                })
+            }
+        };
+
+        tokens.push(Spanned { token, column });
+        i += len;
+    }
+
+    tokens.push(Spanned {
+        token: Token::Eof,
+        column: chars.len() + 1,
+    });
+    Ok(tokens)
+}
+
+/// Read a quoted string starting at its opening quote; returns the value and
+/// the number of characters consumed
+///
+/// Backslashes escape the quote, another backslash, `n` and `t`; any other
+/// escape is kept as written so regex classes like `\d` need no doubling.
+fn read_string(chars: &[char], column: usize) -> Result<(String, usize), ExprError> {
+    let quote = chars[0];
+    let mut value = String::new();
+    let mut i = 1;
+
+    while i < chars.len() {
+        match chars[i] {
+            c if c == quote => return Ok((value, i + 1)),
+            '\\' if i + 1 < chars.len() => {
+                match chars[i + 1] {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    c if c == quote || c == '\\' => value.push(c),
+                    c => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                }
+                i += 2;
+            }
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    Err(ExprError {
+        column,
+        message: "Unterminated string".to_string(),
+    })
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Spanned {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Spanned {
+        let spanned = self.tokens[self.pos].clone();
+        if spanned.token != Token::Eof {
+            self.pos += 1;
+        }
+        spanned
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), ExprError> {
+        let next = self.advance();
+        if next.token == token {
+            Ok(())
+        } else {
+            Err(ExprError {
+                column: next.column,
+                message: format!(
+                    "Expected {} but found {}",
+                    token.describe(),
+                    next.token.describe()
+                ),
+            })
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.parse_and()?;
+        while self.peek().token == Token::Or {
+            self.advance();
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.parse_not()?;
+        while self.peek().token == Token::And {
+            self.advance();
+            node = Node::And(Box::new(node), Box::new(self.parse_not()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_not(&mut self) -> Result<Node, ExprError> {
+        if self.peek().token == Token::Not {
+            self.advance();
+            return Ok(Node::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_condition()
+    }
+
+    fn parse_condition(&mut self) -> Result<Node, ExprError> {
+        if self.peek().token == Token::LParen {
+            self.advance();
+            let node = self.parse_or()?;
+            self.expect(Token::RParen)?;
+            return Ok(node);
+        }
+
+        let left = self.parse_operand()?;
+        match self.peek().token.clone() {
+            Token::Compare(op) => {
+                self.advance();
+                Ok(Node::Compare(left, op, self.parse_operand()?))
+            }
+            Token::Match | Token::NotMatch => {
+                let negate = self.advance().token == Token::NotMatch;
+                let pattern = self.advance();
+                let Token::Str(source) = &pattern.token else {
+                    return Err(ExprError {
+                        column: pattern.column,
+                        message: format!(
+                            "Expected a regex string but found {}",
+                            pattern.token.describe()
+                        ),
+                    });
+                };
+                let regex = Regex::new(source).map_err(|e| ExprError {
+                    column: pattern.column,
+                    message: format!("Invalid regex: {}", e),
+                })?;
+                Ok(Node::Match {
+                    operand: left,
+                    regex,
+                    negate,
+                })
+            }
+            Token::In => {
+                self.advance();
+                Ok(Node::In(left, self.parse_operand()?))
+            }
+            _ => Ok(Node::Truthy(left)),
+        }
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, ExprError> {
+        let next = self.advance();
+        match next.token {
+            Token::Ident(name) => self.parse_path(name),
+            Token::LBracket => {
+                let mut items = Vec::new();
+                if self.peek().token != Token::RBracket {
+                    loop {
+                        let item = self.advance();
+                        items.push(literal(&item).ok_or_else(|| ExprError {
+                            column: item.column,
+                            message: format!(
+                                "Lists may only contain literal values, found {}",
+                                item.token.describe()
+                            ),
+                        })?);
+                        if self.peek().token != Token::Comma {
+                            break;
+                        }
+                        self.advance();
+                    }
+                }
+                self.expect(Token::RBracket)?;
+                Ok(Operand::Literal(Value::Array(items)))
+            }
+            _ => literal(&next)
+                .map(Operand::Literal)
+                .ok_or_else(|| ExprError {
+                    column: next.column,
+                    message: format!(
+                        "Expected a field, value or list but found {}",
+                        next.token.describe()
+                    ),
+                }),
+        }
+    }
+
+    fn parse_path(&mut self, first: String) -> Result<Operand, ExprError> {
+        let mut segments = vec![Segment::Key(first)];
+        loop {
+            match self.peek().token {
+                Token::Dot => {
+                    self.advance();
+                    let next = self.advance();
+                    segments.push(match next.token {
+                        Token::Ident(name) => Segment::Key(name),
+                        Token::Num(n) if n >= 0.0 && n.fract() == 0.0 => Segment::Index(n as usize),
+                        other => {
+                            return Err(ExprError {
+                                column: next.column,
+                                message: format!(
+                                    "Expected a field name after '.' but found {}",
+                                    other.describe()
+                                ),
+                            })
+                        }
+                    });
+                }
+                Token::LBracket => {
+                    self.advance();
+                    let next = self.advance();
+                    segments.push(match next.token {
+                        Token::Str(key) => Segment::Key(key),
+                        Token::Num(n) if n >= 0.0 && n.fract() == 0.0 => Segment::Index(n as usize),
+                        other => {
+                            return Err(ExprError {
+                                column: next.column,
+                                message: format!(
+                                    "Expected an index or quoted key but found {}",
+                                    other.describe()
+                                ),
+                            })
+                        }
+                    });
+                    self.expect(Token::RBracket)?;
+                }
+                _ => return Ok(Operand::Path(segments)),
+            }
+        }
+    }
+}
+
+fn literal(spanned: &Spanned) -> Option<Value> {
+    match &spanned.token {
+        Token::Str(s) => Some(Value::String(s.clone())),
+        Token::Num(n) => serde_json::Number::from_f64(*n).map(Value::Number),
+        Token::True => Some(Value::Bool(true)),
+        Token::False => Some(Value::Bool(false)),
+        Token::Null => Some(Value::Null),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn eval(source: &str, context: &Value) -> bool {
+        Expr::parse(source).unwrap().evaluate(context)
+    }
+
+    fn error(source: &str) -> String {
+        Expr::parse(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_example_expression() {
+        let source = r#"hook == "PreToolUse" and tool_input.command =~ "rm -rf" or status in ["APIError","SessionLimitReached"]"#;
+
+        let dangerous = json!({"hook": "PreToolUse", "tool_input": {"command": "rm -rf /tmp/x"}});
+        let safe = json!({"hook": "PreToolUse", "tool_input": {"command": "ls"}});
+        let failed = json!({"hook": "Stop", "status": "APIError"});
+        let done = json!({"hook": "Stop", "status": "TaskComplete"});
+
+        assert!(eval(source, &dangerous));
+        assert!(!eval(source, &safe));
+        assert!(eval(source, &failed));
+        assert!(!eval(source, &done));
+        assert!(Expr::parse(source).unwrap().references("status"));
+        assert!(!Expr::parse("hook == 'Stop'").unwrap().references("status"));
+    }
+
+    #[test]
+    fn test_operators() {
+        let context = json!({
+            "message": "Waiting for input",
+            "count": 3,
+            "flag": false,
+            "tool_input": {"edits": [{"path": "src/main.rs"}]}
+        });
+
+        assert!(eval("not (count < 2) && !flag", &context));
+        assert!(eval("count >= 3.0 and count != 4", &context));
+        assert!(eval(
+            "message =~ '^waiting' or message =~ '(?i)^waiting'",
+            &context
+        ));
+        assert!(eval(r#"message !~ "\d""#, &context));
+        assert!(eval("'input' in message", &context));
+        assert!(eval("tool_input.edits[0].path == 'src/main.rs'", &context));
+        assert!(eval("tool_input.edits.0.path =~ '\\.rs$'", &context));
+        assert!(eval("tool_input and not missing.field", &context));
+        assert!(eval("missing == null and not (missing =~ '.*')", &context));
+        assert!(!eval("'nope' in []", &context));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            error("hook = 'Stop'"),
+            "Unexpected '=', use '==' to compare at column 6"
+        );
+        assert_eq!(error("hook == 'Stop"), "Unterminated string at column 9");
+        assert_eq!(
+            error("hook == 'Stop' and"),
+            "Expected a field, value or list but found end of expression at column 19"
+        );
+        assert_eq!(
+            error("(hook == 'Stop'"),
+            "Expected ')' but found end of expression at column 16"
+        );
+        assert_eq!(
+            error("hook == 'Stop' message"),
+            "Unexpected 'message', expected 'and', 'or' or the end of the expression at column 16"
+        );
+        assert_eq!(
+            error("tool_name =~ tool"),
+            "Expected a regex string but found 'tool' at column 14"
+        );
+        assert!(error("message =~ '(unclosed'").starts_with("Invalid regex:"));
+        assert_eq!(
+            error("status in [hook]"),
+            "Lists may only contain literal values, found 'hook' at column 12"
+        );
+    }
+}
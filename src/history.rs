@@ -0,0 +1,530 @@
+//! Delivery history log
+//!
+//! Every send attempt is appended as one JSON line to `history.jsonl` in the
+//! state directory, so a missing notification can be traced after the fact
+//! with the `history` command. Appends take the same kind of lock file as the
+//! runtime state, so concurrent hooks neither interleave records nor rotate
+//! the log twice. Once the log grows past the configured size it is rotated
+//! to `history.jsonl.1`, which queries still read.
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::channels::ChannelResult;
+use crate::config::{get_state_dir, HistoryConfig, RenderedMessage};
+use crate::error::{NotificationError, Result};
+use crate::hooks::{HookInput, HookType};
+use crate::state::StateLock;
+
+/// How a delivery attempt ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryOutcome {
+    /// The channel accepted the notification
+    Sent,
+    /// The channel reported an error
+    Failed,
+    /// The send was still running when the hook's dispatch budget ran out
+    Abandoned,
+    /// The notification was not sent to the channel; `reason` says why
+    Skipped,
+}
+
+/// One delivery attempt to one channel
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeliveryRecord {
+    /// Time the send started in milliseconds since the Unix epoch
+    pub timestamp_ms: i64,
+    pub session_id: String,
+    pub hook_type: HookType,
+    /// Routing rules that selected the notification
+    #[serde(default)]
+    pub rules: Vec<String>,
+    pub channel_id: String,
+    /// Primary channel this send stood in for
+    #[serde(default)]
+    pub fallback_for: Option<String>,
+    pub title: String,
+    pub body: String,
+    pub outcome: DeliveryOutcome,
+    pub latency_ms: u64,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Why a skipped notification was not sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Hook input that was delivered, for `replay`
+    #[serde(default)]
+    pub input: Option<HookInput>,
+}
+
+/// Filter for [`History::query`]
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    /// Session id or a prefix of it
+    pub session_id: Option<String>,
+    pub channel_id: Option<String>,
+    /// Only attempts that did not deliver
+    pub failed_only: bool,
+    /// Earliest send time in milliseconds since the Unix epoch
+    pub since_ms: Option<i64>,
+    /// Return at most this many of the most recent matches
+    pub limit: Option<usize>,
+}
+
+impl HistoryFilter {
+    fn matches(&self, record: &DeliveryRecord) -> bool {
+        self.session_id
+            .as_ref()
+            .is_none_or(|id| record.session_id.starts_with(id.as_str()))
+            && self
+                .channel_id
+                .as_ref()
+                .is_none_or(|id| &record.channel_id == id)
+            && (!self.failed_only || record.outcome != DeliveryOutcome::Sent)
+            && self
+                .since_ms
+                .is_none_or(|since| record.timestamp_ms >= since)
+    }
+}
+
+/// Append-only log of delivery attempts
+#[derive(Debug, Clone)]
+pub struct History {
+    path: PathBuf,
+    settings: HistoryConfig,
+}
+
+impl History {
+    /// Create a log backed by a specific file
+    pub fn new(path: PathBuf, settings: HistoryConfig) -> Self {
+        Self { path, settings }
+    }
+
+    /// Get the default history file path
+    /// Returns ~/.claude-code-notifications/history.jsonl
+    pub fn default_path() -> PathBuf {
+        get_state_dir().join("history.jsonl")
+    }
+
+    /// Describe a notification about to be sent, for recording its attempts
+    pub fn delivery(
+        &self,
+        input: &HookInput,
+        rules: &[String],
+        rendered: &RenderedMessage,
+    ) -> Delivery {
+        Delivery {
+            history: self.clone(),
+            input: input.clone(),
+            rules: rules.to_vec(),
+            title: rendered.title.clone(),
+            body: rendered.body.clone(),
+        }
+    }
+
+    /// Append a record, rotating the log first if it is full
+    pub fn append(&self, record: &DeliveryRecord) -> Result<()> {
+        if !self.settings.enabled {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let _lock = StateLock::acquire(&self.path.with_extension("lock"))?;
+        let max_bytes = self.settings.max_file_kb * 1024;
+        if fs::metadata(&self.path).is_ok_and(|m| m.len() >= max_bytes) {
+            fs::rename(&self.path, self.rotated_path())?;
+        }
+
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(&line)?;
+        Ok(())
+    }
+
+    /// Read matching records, oldest first
+    pub fn query(&self, filter: &HistoryFilter) -> Result<Vec<DeliveryRecord>> {
+        let mut records = Vec::new();
+        for path in [self.rotated_path(), self.path.clone()] {
+            records.extend(
+                read_records(&path)?
+                    .into_iter()
+                    .filter(|r| filter.matches(r)),
+            );
+        }
+
+        // Concurrent hooks may append slightly out of order
+        records.sort_by_key(|r| r.timestamp_ms);
+        if let Some(limit) = filter.limit {
+            records.drain(..records.len().saturating_sub(limit));
+        }
+        Ok(records)
+    }
+
+    fn rotated_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".1");
+        path.into()
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(Self::default_path(), HistoryConfig::default())
+    }
+}
+
+/// A notification being delivered, shared by a channel's send and its fallbacks
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    history: History,
+    input: HookInput,
+    rules: Vec<String>,
+    title: String,
+    body: String,
+}
+
+impl Delivery {
+    /// Start timing a send to a channel
+    pub fn begin(&self, channel_id: &str, fallback_for: Option<&str>) -> Attempt {
+        Attempt {
+            history: self.history.clone(),
+            record: Some(self.record(channel_id, fallback_for)),
+            started: Instant::now(),
+        }
+    }
+
+    /// Record that the notification was not sent to a channel
+    pub fn skip(&self, channel_id: &str, fallback_for: Option<&str>, reason: &str) {
+        let record = DeliveryRecord {
+            outcome: DeliveryOutcome::Skipped,
+            reason: Some(reason.to_string()),
+            ..self.record(channel_id, fallback_for)
+        };
+        if let Err(e) = self.history.append(&record) {
+            eprintln!("Warning: Could not record delivery history: {}", e);
+        }
+    }
+
+    fn record(&self, channel_id: &str, fallback_for: Option<&str>) -> DeliveryRecord {
+        DeliveryRecord {
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            session_id: self.input.common.session_id.clone(),
+            hook_type: self.input.hook_event_name,
+            rules: self.rules.clone(),
+            channel_id: channel_id.to_string(),
+            fallback_for: fallback_for.map(|id| id.to_string()),
+            title: self.title.clone(),
+            body: self.body.clone(),
+            outcome: DeliveryOutcome::Abandoned,
+            latency_ms: 0,
+            error: None,
+            reason: None,
+            input: Some(self.input.clone()),
+        }
+    }
+}
+
+/// A send in progress
+///
+/// Records itself when finished. If it is dropped first, the hook process
+/// stopped waiting for it and it is recorded as abandoned.
+pub struct Attempt {
+    history: History,
+    record: Option<DeliveryRecord>,
+    started: Instant,
+}
+
+impl Attempt {
+    /// Record the outcome of the send
+    pub fn finish<T>(mut self, result: &ChannelResult<T>) {
+        if let Some(mut record) = self.record.take() {
+            match result {
+                Ok(_) => record.outcome = DeliveryOutcome::Sent,
+                Err(e) => {
+                    record.outcome = DeliveryOutcome::Failed;
+                    record.error = Some(e.to_string());
+                }
+            }
+            self.write(record);
+        }
+    }
+
+    fn write(&self, mut record: DeliveryRecord) {
+        record.latency_ms = self.started.elapsed().as_millis() as u64;
+        if let Err(e) = self.history.append(&record) {
+            eprintln!("Warning: Could not record delivery history: {}", e);
+        }
+    }
+}
+
+impl Drop for Attempt {
+    fn drop(&mut self) {
+        if let Some(mut record) = self.record.take() {
+            record.error = Some("Dispatch budget ran out before the send finished".to_string());
+            self.write(record);
+        }
+    }
+}
+
+/// Distinct hook inputs of a set of records, in the order first seen
+///
+/// A dispatch records one attempt per channel, all with the same input.
+/// The severity routing gave the input is dropped, so the current rules
+/// decide it again.
+pub fn recorded_inputs(records: &[DeliveryRecord]) -> Vec<HookInput> {
+    let mut seen = HashSet::new();
+    records
+        .iter()
+        .filter_map(|record| record.input.clone())
+        .map(|input| HookInput {
+            severity: None,
+            ..input
+        })
+        .filter(|input| seen.insert(serde_json::to_string(input).unwrap_or_default()))
+        .collect()
+}
+
+/// Parse a `--since` value: a duration such as `30m`, `2h` or `7d`, a date
+/// (`2024-05-01`, local midnight) or an RFC 3339 timestamp
+pub fn parse_since(value: &str, now: DateTime<Local>) -> Result<i64> {
+    let invalid = || {
+        NotificationError::InvalidInput(format!(
+            "Invalid --since value '{}': use 30m, 2h, 7d, 2024-05-01 or an RFC 3339 time",
+            value
+        ))
+    };
+    let value = value.trim();
+
+    if let Some(unit) = value.chars().last().filter(|c| c.is_ascii_alphabetic()) {
+        if let Ok(amount) = value[..value.len() - 1].parse::<i64>() {
+            let seconds = match unit {
+                's' => 1,
+                'm' => 60,
+                'h' => 3600,
+                'd' => 86_400,
+                'w' => 7 * 86_400,
+                _ => return Err(invalid()),
+            };
+            return Ok(now.timestamp_millis() - amount * seconds * 1000);
+        }
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.timestamp_millis());
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .map(|time| time.timestamp_millis())
+        .ok_or_else(invalid)
+}
+
+/// Read the records of one log file, skipping lines that do not parse
+fn read_records(path: &Path) -> Result<Vec<DeliveryRecord>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ChannelError;
+
+    fn history(dir: &Path, max_file_kb: u64) -> History {
+        History::new(
+            dir.join("history.jsonl"),
+            HistoryConfig {
+                enabled: true,
+                max_file_kb,
+            },
+        )
+    }
+
+    fn delivery(history: &History, session_id: &str) -> Delivery {
+        let input = HookInput::stop(session_id.to_string(), None, None);
+        let rendered = RenderedMessage {
+            title: "Claude Code".to_string(),
+            body: "Task completed".to_string(),
+        };
+        history.delivery(&input, &["Team chat".to_string()], &rendered)
+    }
+
+    #[test]
+    fn test_records_attempts() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = history(dir.path(), 1024);
+        let delivery = delivery(&history, "session-1");
+
+        delivery
+            .begin("wechat", None)
+            .finish::<()>(&Err(ChannelError::Timeout));
+        delivery.begin("dingtalk", Some("wechat")).finish(&Ok(()));
+        drop(delivery.begin("feishu", None));
+        delivery.skip("system", None, "quiet hours");
+
+        let records = history.query(&HistoryFilter::default()).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].outcome, DeliveryOutcome::Failed);
+        assert_eq!(
+            records[0].error.as_deref(),
+            Some("Channel operation timeout")
+        );
+        assert_eq!(records[0].rules, vec!["Team chat"]);
+        assert_eq!(records[1].outcome, DeliveryOutcome::Sent);
+        assert_eq!(records[1].fallback_for.as_deref(), Some("wechat"));
+        assert_eq!(records[2].outcome, DeliveryOutcome::Abandoned);
+        assert_eq!(records[3].outcome, DeliveryOutcome::Skipped);
+        assert_eq!(records[3].reason.as_deref(), Some("quiet hours"));
+        assert_eq!(records[3].error, None);
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = history(dir.path(), 1024);
+
+        delivery(&history, "abc-123")
+            .begin("wechat", None)
+            .finish::<()>(&Err(ChannelError::Timeout));
+        delivery(&history, "abc-123")
+            .begin("system", None)
+            .finish(&Ok(()));
+        delivery(&history, "def-456")
+            .begin("wechat", None)
+            .finish(&Ok(()));
+
+        let query = |filter: HistoryFilter| history.query(&filter).unwrap().len();
+        assert_eq!(
+            query(HistoryFilter {
+                session_id: Some("abc".to_string()),
+                ..Default::default()
+            }),
+            2
+        );
+        assert_eq!(
+            query(HistoryFilter {
+                channel_id: Some("wechat".to_string()),
+                failed_only: true,
+                ..Default::default()
+            }),
+            1
+        );
+        assert_eq!(
+            query(HistoryFilter {
+                since_ms: Some(i64::MAX),
+                ..Default::default()
+            }),
+            0
+        );
+        assert_eq!(
+            query(HistoryFilter {
+                limit: Some(1),
+                ..Default::default()
+            }),
+            1
+        );
+    }
+
+    #[test]
+    fn test_concurrent_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = history(dir.path(), 1024);
+
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let delivery = delivery(&history, &format!("session-{}", writer));
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        delivery.begin("wechat", None).finish(&Ok(()));
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let records = history.query(&HistoryFilter::default()).unwrap();
+        assert_eq!(records.len(), 100);
+        assert!(!dir.path().join("history.lock").exists());
+    }
+
+    #[test]
+    fn test_rotation_keeps_previous_file() {
+        let dir = tempfile::tempdir().unwrap();
+        // Every append after the first finds the log full
+        let history = history(dir.path(), 0);
+
+        for channel_id in ["a", "b", "c"] {
+            delivery(&history, "s")
+                .begin(channel_id, None)
+                .finish(&Ok(()));
+        }
+
+        let records = history.query(&HistoryFilter::default()).unwrap();
+        let channels: Vec<&str> = records.iter().map(|r| r.channel_id.as_str()).collect();
+        assert_eq!(channels, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_recorded_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = history(dir.path(), 1024);
+
+        let first = delivery(&history, "s1");
+        first.begin("wechat", None).finish(&Ok(()));
+        first.begin("system", None).finish(&Ok(()));
+        delivery(&history, "s2")
+            .begin("wechat", None)
+            .finish(&Ok(()));
+
+        let records = history.query(&HistoryFilter::default()).unwrap();
+        let sessions: Vec<String> = recorded_inputs(&records)
+            .into_iter()
+            .map(|input| input.common.session_id)
+            .collect();
+        assert_eq!(sessions, vec!["s1", "s2"]);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = Local.with_ymd_and_hms(2024, 5, 2, 12, 0, 0).unwrap();
+        let now_ms = now.timestamp_millis();
+
+        assert_eq!(parse_since("30m", now).unwrap(), now_ms - 30 * 60_000);
+        assert_eq!(parse_since("2d", now).unwrap(), now_ms - 2 * 86_400_000);
+        assert_eq!(
+            parse_since("2024-05-02", now).unwrap(),
+            now_ms - 12 * 3_600_000
+        );
+        assert_eq!(
+            parse_since("2024-05-02T10:00:00Z", now).unwrap(),
+            chrono::Utc
+                .with_ymd_and_hms(2024, 5, 2, 10, 0, 0)
+                .unwrap()
+                .timestamp_millis()
+        );
+        assert!(parse_since("yesterday", now).is_err());
+        assert!(parse_since("5y", now).is_err());
+    }
+}
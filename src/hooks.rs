@@ -3,7 +3,11 @@
 //! This module defines the different hook types that Claude Code can send
 //! and their corresponding data structures.
 
+use crate::analyzer::{analyze_transcript, Status};
+use crate::severity::Severity;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
 
 /// Type of hook being invoked by Claude Code
 #[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
@@ -19,6 +23,8 @@ pub enum HookType {
     SubagentStop,
     /// Hook invoked when permission is requested
     PermissionRequest,
+    /// Hook invoked when a session ends
+    SessionEnd,
 }
 
 /// Common fields present in all hook types
@@ -37,6 +43,22 @@ pub struct CommonHookFields {
     pub permission_mode: Option<String>,
 }
 
+impl CommonHookFields {
+    /// Name of the project the hook ran in
+    ///
+    /// This is the directory name of the git repository containing `cwd`,
+    /// or of `cwd` itself outside a repository.
+    pub fn project(&self) -> Option<String> {
+        let cwd = std::path::Path::new(self.cwd.as_deref()?);
+        let root = cwd
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())
+            .unwrap_or(cwd);
+        root.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+}
+
 /// Data specific to Notification hooks
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct NotificationData {
@@ -103,6 +125,13 @@ pub struct PermissionRequestData {
     pub context: Option<String>,
 }
 
+/// Data specific to SessionEnd hooks
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SessionEndData {
+    /// Why the session ended: "clear", "logout", "prompt_input_exit", or "other"
+    pub reason: String,
+}
+
 /// Enum representing the type-specific data for each hook type
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(untagged)]
@@ -111,6 +140,8 @@ pub enum HookData {
     Notification(NotificationData),
     /// PreToolUse-specific data
     PreToolUse(PreToolUseData),
+    /// SessionEnd-specific data (before Stop, which matches any input)
+    SessionEnd(SessionEndData),
     /// Stop-specific data
     Stop(StopData),
     /// SubagentStop-specific data
@@ -130,9 +161,52 @@ pub struct HookInput {
     /// Type-specific data (depends on hook_event_name)
     #[serde(flatten)]
     pub data: HookData,
+    /// Severity decided by routing, or given by the caller to skip the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    /// Transcript status, analyzed on first use and kept by clones
+    #[serde(skip)]
+    pub(crate) status: OnceLock<Option<Status>>,
+    /// Environment routing rules read instead of this process's, when the
+    /// hook ran in another process
+    #[serde(skip)]
+    pub(crate) env: Option<HashMap<String, String>>,
 }
 
 impl HookInput {
+    /// Route with environment variables captured by the hook process
+    ///
+    /// Variables missing from `env` count as unset.
+    pub fn with_env(self, env: HashMap<String, String>) -> Self {
+        Self {
+            env: Some(env),
+            ..self
+        }
+    }
+
+    /// Environment variable as the hook process saw it
+    pub(crate) fn env_var(&self, name: &str) -> Option<String> {
+        match &self.env {
+            Some(env) => env.get(name).cloned(),
+            None => std::env::var_os(name).map(|value| value.to_string_lossy().into_owned()),
+        }
+    }
+
+    /// Transcript status of a Stop or SubagentStop hook
+    ///
+    /// The transcript is analyzed at most once per input, however many
+    /// rules, templates and channels ask for it.
+    pub fn transcript_status(&self) -> Option<Status> {
+        *self.status.get_or_init(|| match self.hook_event_name {
+            HookType::Stop | HookType::SubagentStop => self
+                .common
+                .transcript_path
+                .as_deref()
+                .and_then(|path| analyze_transcript(path).ok()),
+            _ => None,
+        })
+    }
+
     /// Create a notification hook input (for testing)
     pub fn notification(
         session_id: String,
@@ -153,6 +227,9 @@ impl HookInput {
                 title,
                 notification_type: None,
             }),
+            severity: None,
+            status: OnceLock::new(),
+            env: None,
         }
     }
 
@@ -176,6 +253,9 @@ impl HookInput {
                 tool_input: None,
                 tool_use_id: None,
             }),
+            severity: None,
+            status: OnceLock::new(),
+            env: None,
         }
     }
 
@@ -194,6 +274,9 @@ impl HookInput {
                 permission_mode: None,
             },
             data: HookData::Stop(StopData::default()),
+            severity: None,
+            status: OnceLock::new(),
+            env: None,
         }
     }
 
@@ -213,6 +296,9 @@ impl HookInput {
                 permission_mode: None,
             },
             data: HookData::SubagentStop(SubagentStopData::default()),
+            severity: None,
+            status: OnceLock::new(),
+            env: None,
         }
     }
 
@@ -237,6 +323,30 @@ impl HookInput {
                 description,
                 context,
             }),
+            severity: None,
+            status: OnceLock::new(),
+            env: None,
+        }
+    }
+
+    /// Create a SessionEnd hook input (for testing)
+    pub fn session_end(
+        session_id: String,
+        transcript_path: Option<String>,
+        reason: String,
+    ) -> Self {
+        Self {
+            hook_event_name: HookType::SessionEnd,
+            common: CommonHookFields {
+                session_id,
+                transcript_path,
+                cwd: None,
+                permission_mode: None,
+            },
+            data: HookData::SessionEnd(SessionEndData { reason }),
+            severity: None,
+            status: OnceLock::new(),
+            env: None,
         }
     }
 }
@@ -0,0 +1,221 @@
+//! JSONPath selectors for matching inside tool input
+//!
+//! Supports the common subset of JSONPath: the root `$`, child keys
+//! (`$.command`, `$['file path']`), array indices (`$.edits[0]`, `$.edits[-1]`
+//! from the end), wildcards (`$.edits[*]`, `$.*`) and recursive descent
+//! (`$..file_path`). A selector can pick several values; a rule condition
+//! holds when any of them matches.
+
+use serde_json::Value;
+
+/// Compiled JSONPath selector
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    /// Apply the step to the current values and all their descendants
+    Descendant(Box<Step>),
+}
+
+impl JsonPath {
+    /// Compile a selector, returning a description of what is wrong with it
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let chars: Vec<char> = path.trim().chars().collect();
+        let error = |pos: usize, message: &str| {
+            format!("{} at column {} of '{}'", message, pos + 1, path.trim())
+        };
+
+        if chars.first() != Some(&'$') {
+            return Err(error(0, "Expected '$'"));
+        }
+
+        let mut steps = Vec::new();
+        let mut pos = 1;
+        while pos < chars.len() {
+            let descendant = chars[pos] == '.' && chars.get(pos + 1) == Some(&'.');
+            let step = match chars[pos] {
+                '.' => {
+                    pos += if descendant { 2 } else { 1 };
+                    match chars.get(pos) {
+                        Some('*') => {
+                            pos += 1;
+                            Step::Wildcard
+                        }
+                        Some('[') if descendant => parse_bracket(&chars, &mut pos)
+                            .map_err(|(at, message)| error(at, &message))?,
+                        _ => {
+                            let start = pos;
+                            while pos < chars.len()
+                                && (chars[pos].is_alphanumeric() || "_-$".contains(chars[pos]))
+                            {
+                                pos += 1;
+                            }
+                            if pos == start {
+                                return Err(error(start, "Expected a key name"));
+                            }
+                            Step::Key(chars[start..pos].iter().collect())
+                        }
+                    }
+                }
+                '[' => {
+                    parse_bracket(&chars, &mut pos).map_err(|(at, message)| error(at, &message))?
+                }
+                _ => return Err(error(pos, "Expected '.' or '['")),
+            };
+            steps.push(if descendant {
+                Step::Descendant(Box::new(step))
+            } else {
+                step
+            });
+        }
+
+        Ok(Self { steps })
+    }
+
+    /// Select the values the path points to
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+        for step in &self.steps {
+            current = current
+                .into_iter()
+                .flat_map(|value| apply(step, value))
+                .collect();
+        }
+        current
+    }
+}
+
+/// Parse a `[...]` step starting at the opening bracket
+fn parse_bracket(chars: &[char], pos: &mut usize) -> Result<Step, (usize, String)> {
+    let open = *pos;
+    let close = chars[open..]
+        .iter()
+        .position(|&c| c == ']')
+        .map(|offset| open + offset)
+        .ok_or((open, "Unterminated '['".to_string()))?;
+    let inner: String = chars[open + 1..close].iter().collect();
+    let inner = inner.trim();
+    *pos = close + 1;
+
+    if inner == "*" {
+        return Ok(Step::Wildcard);
+    }
+    for quote in ['\'', '"'] {
+        if let Some(key) = inner
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return Ok(Step::Key(key.to_string()));
+        }
+    }
+    inner.parse::<i64>().map(Step::Index).map_err(|_| {
+        (
+            open,
+            format!("Expected an index, '*' or a quoted key in '[{}]'", inner),
+        )
+    })
+}
+
+/// Apply a single step to a value
+fn apply<'a>(step: &Step, value: &'a Value) -> Vec<&'a Value> {
+    match (step, value) {
+        (Step::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+        (Step::Index(index), Value::Array(items)) => {
+            let index = if *index < 0 {
+                items.len() as i64 + index
+            } else {
+                *index
+            };
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| items.get(index))
+                .into_iter()
+                .collect()
+        }
+        (Step::Wildcard, Value::Object(map)) => map.values().collect(),
+        (Step::Wildcard, Value::Array(items)) => items.iter().collect(),
+        (Step::Descendant(inner), value) => {
+            let mut all = Vec::new();
+            collect_descendants(value, &mut all);
+            all.into_iter()
+                .flat_map(|value| apply(inner, value))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Collect a value and everything nested inside it
+fn collect_descendants<'a>(value: &'a Value, into: &mut Vec<&'a Value>) {
+    into.push(value);
+    match value {
+        Value::Object(map) => map
+            .values()
+            .for_each(|child| collect_descendants(child, into)),
+        Value::Array(items) => items
+            .iter()
+            .for_each(|child| collect_descendants(child, into)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(path: &str, value: &Value) -> Vec<Value> {
+        JsonPath::parse(path)
+            .unwrap()
+            .select(value)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_select() {
+        let input = json!({
+            "command": "git push",
+            "file path": "/tmp/a",
+            "edits": [
+                {"file_path": "/srv/prod/a.rs", "old": 1},
+                {"file_path": "/srv/dev/b.rs", "nested": {"file_path": "/c"}}
+            ]
+        });
+
+        assert_eq!(select("$.command", &input), vec![json!("git push")]);
+        assert_eq!(select("$['file path']", &input), vec![json!("/tmp/a")]);
+        assert_eq!(select("$.edits[0].old", &input), vec![json!(1)]);
+        assert_eq!(
+            select("$.edits[-1].file_path", &input),
+            vec![json!("/srv/dev/b.rs")]
+        );
+        assert_eq!(
+            select("$.edits[*].file_path", &input),
+            vec![json!("/srv/prod/a.rs"), json!("/srv/dev/b.rs")]
+        );
+        assert_eq!(select("$..file_path", &input).len(), 3);
+        assert_eq!(select("$", &input), vec![input.clone()]);
+        assert!(select("$.missing.deeper", &input).is_empty());
+        assert!(select("$.edits[5]", &input).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        assert!(JsonPath::parse("command").is_err());
+        assert!(JsonPath::parse("$.").is_err());
+        assert!(JsonPath::parse("$.edits[0").is_err());
+        assert!(JsonPath::parse("$.edits[x]").is_err());
+        assert_eq!(
+            JsonPath::parse("$command").unwrap_err(),
+            "Expected '.' or '[' at column 2 of '$command'"
+        );
+    }
+}
@@ -5,31 +5,61 @@
 //! optional sound playback.
 
 mod analyzer;
+mod background;
 mod channels;
+mod circuit;
 mod config;
+mod daemon;
+mod dedup;
 mod error;
+mod escalation;
+mod expr;
+mod history;
 mod hooks;
+mod jsonpath;
 mod logging;
+mod outbox;
+mod pattern;
+mod ratelimit;
 mod router;
+mod schedule;
+mod severity;
+mod state;
 mod summary;
 mod transcript;
 mod web;
 
 use notify_rust::{Notification, Timeout};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Read;
 use std::process::Command;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
 
 pub use analyzer::*;
+pub use background::spawn_detached_worker;
 pub use channels::*;
+pub use circuit::{circuit_status, CircuitBreaker, CircuitState, CircuitStatus};
 pub use config::*;
-pub use error::{NotificationError, Result};
+pub use daemon::{forward_to_daemon, get_socket_path, run_daemon, DaemonRequest};
+pub use error::{ChannelError, NotificationError, Result};
+pub use escalation::PendingEscalation;
+pub use expr::{Expr, ExprError};
+pub use history::{
+    parse_since, recorded_inputs, Attempt, Delivery, DeliveryOutcome, DeliveryRecord, History,
+    HistoryFilter,
+};
 pub use hooks::*;
-pub use router::ChannelRouter;
+pub use outbox::{EntryCategory, OutboxEntry, OutboxKind};
+pub use ratelimit::{RateDecision, TokenBucket};
+pub use router::{ChannelRouter, RouteDecision, RuleEvaluation, RuleOutcome};
+pub use schedule::Schedule;
+pub use severity::Severity;
+pub use state::{RuntimeState, StateStore};
 pub use summary::*;
 pub use transcript::*;
 pub use web::start_web_server;
@@ -134,29 +164,59 @@ pub struct ChannelManager {
     registry: ChannelRegistry,
     config: AppConfig,
     router: ChannelRouter,
+    state: StateStore,
+    history: History,
+    flush_timers: bool,
 }
 
 impl ChannelManager {
     /// Create a new channel manager by loading configuration
     pub fn load() -> Result<Self> {
-        let config = load_config()?;
+        Self::from_loaded_config(load_config()?)
+    }
+
+    /// Create a new channel manager from configuration the caller already
+    /// loaded, enabling debug logging as configured
+    pub fn from_loaded_config(config: AppConfig) -> Result<Self> {
         logging::init_debug(&config);
         debug_log!("Configuration loaded, debug mode: {}", config.debug);
         Self::from_config(config)
     }
 
     /// Create a new channel manager from a specific configuration
+    ///
+    /// Invalid routing rules are skipped with a warning; `status` and the
+    /// Web UI report them.
     pub fn from_config(config: AppConfig) -> Result<Self> {
         let registry = ChannelRegistry::new();
-        let router = ChannelRouter::new(&config);
+        let router = ChannelRouter::lenient(&config);
+        let history = History::new(History::default_path(), config.history.clone());
 
         Ok(Self {
             registry,
             config,
             router,
+            state: StateStore::default(),
+            history,
+            flush_timers: true,
         })
     }
 
+    /// Use a specific state store for persisted runtime state
+    pub fn with_state_store(mut self, state: StateStore) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Enable or disable detached flush timers for escalation steps
+    ///
+    /// Timers are on by default so escalations fire on time without a daemon.
+    /// The daemon disables them because it flushes on its own schedule.
+    pub fn with_flush_timers(mut self, enabled: bool) -> Self {
+        self.flush_timers = enabled;
+        self
+    }
+
     /// Send notification through appropriate channels
     ///
     /// This method determines which channels should receive the notification
@@ -172,171 +232,1288 @@ impl ChannelManager {
         let start = std::time::Instant::now();
 
         // Match channels based on routing rules
-        let matched_channels = self.router.match_channels(input, &self.config)?;
-        debug_context!("ChannelManager", "Matched channels: {:?}", matched_channels);
-
-        // Create template engine with global templates
-        let template_engine = TemplateEngine::new(self.config.global_templates.clone());
+        let route = self.router.route(input, &self.config)?;
+        debug_context!("ChannelManager", "Matched channels: {:?}", route.channels);
+        self.cancel_escalations(input, &route);
+        let input = &with_severity(input, route.severity);
+
+        if self.is_duplicate(input) {
+            debug_context!("ChannelManager", "Duplicate notification suppressed");
+            self.record_skipped(input, &route.channels, &route.rules, DUPLICATE_REASON);
+            return Ok(());
+        }
 
-        // Wrap input in Arc for safe sharing across tasks
-        let input = Arc::new(input.clone());
-        let template_engine = Arc::new(template_engine);
+        self.defer_routed(input, &route);
+        self.register_escalations(input, &route);
+        let active_channels = self.hold_back_channels(input, &route.channels, &route.rules);
+        let quiet = active_channels.len() < route.channels.len() || !route.deferred.is_empty();
 
         // Send to all matched channels in parallel
-        let mut tasks = Vec::new();
+        let budget = Some(self.dispatch_budget());
+        let mut tasks = self.spawn_channel_tasks(
+            input,
+            active_channels,
+            &route.rules,
+            &route.fallbacks,
+            false,
+            budget,
+        );
+        if tasks.is_empty() && !quiet {
+            eprintln!("Warning: No enabled channels found for notification");
+        }
 
-        for channel_id in matched_channels {
-            // Get channel configuration
-            let channel_config = self
-                .config
-                .channels
-                .get(&channel_id)
-                .cloned()
-                .unwrap_or_default();
+        // Deliver deferred notifications whose quiet hours have ended
+        tasks.extend(self.spawn_due_tasks(ended_session(input), budget));
 
-            // Get the channel type from config (defaults to channel_id for backward compatibility)
-            let channel_type = if channel_config.channel_type.is_empty() {
-                channel_id.clone()
-            } else {
-                channel_config.channel_type.clone()
-            };
+        // Wait for all tasks within the dispatch budget (system channel is instant)
+        if tasks.is_empty() {
+            return Ok(());
+        }
 
-            // Create a new channel instance based on channel_type
-            if let Some(channel) = self.registry.create_channel(&channel_type) {
-                // Skip disabled channels
-                if !channel.is_enabled(&channel_config) {
-                    continue;
+        // Log errors but don't fail on partial failures or timeout
+        if let Some(task_results) = self.join_within_budget(tasks).await {
+            for (channel_type, result) in task_results {
+                if let Err(e) = result {
+                    eprintln!("Channel {} error: {}", channel_type, e);
+                    debug_context!("ChannelManager", "Channel {} error: {}", channel_type, e);
+                } else {
+                    debug_context!("ChannelManager", "Channel {} succeeded", channel_type);
                 }
+            }
+        }
+        debug_context!(
+            "ChannelManager",
+            "send_notification_async() completed in {:?}",
+            start.elapsed()
+        );
+        Ok(())
+    }
 
-                let input = Arc::clone(&input);
-                let template_engine = Arc::clone(&template_engine);
+    /// Send notification to specific channels (bypasses routing rules)
+    pub fn send_to_channels(&self, input: &HookInput, channel_ids: Vec<String>) -> Result<()> {
+        let runtime = Runtime::new()?;
+        runtime.block_on(self.send_to_channels_async(input, channel_ids))
+    }
 
-                tasks.push(tokio::spawn(async move {
-                    let result = channel
-                        .send(&input, &channel_config, &template_engine)
-                        .await;
-                    (channel_id, result)
-                }));
-            }
+    /// Send notification to specific channels (async version)
+    pub async fn send_to_channels_async(
+        &self,
+        input: &HookInput,
+        channel_ids: Vec<String>,
+    ) -> Result<()> {
+        self.cancel_escalations(input, &RouteDecision::default());
+
+        // Deduplicate channels
+        let channel_ids = self.router.override_channels(channel_ids);
+        let input = &with_severity(input, Severity::analyze(input));
+
+        if self.is_duplicate(input) {
+            debug_context!("ChannelManager", "Duplicate notification suppressed");
+            self.record_skipped(input, &channel_ids, &[], DUPLICATE_REASON);
+            return Ok(());
         }
 
-        // Wait for all tasks with timeout (2 seconds - system channel is instant, webhooks run in background)
+        let active_channels = self.hold_back_channels(input, &channel_ids, &[]);
+        let quiet = active_channels.len() < channel_ids.len();
+
+        let budget = Some(self.dispatch_budget());
+        let mut tasks =
+            self.spawn_channel_tasks(input, active_channels, &[], &HashMap::new(), true, budget);
+        if tasks.is_empty() && !quiet {
+            eprintln!("Warning: No valid channels specified");
+        }
+        tasks.extend(self.spawn_due_tasks(ended_session(input), budget));
+
+        // Wait for all tasks within the dispatch budget (system channel is instant)
         if tasks.is_empty() {
-            eprintln!("Warning: No enabled channels found for notification");
             return Ok(());
         }
 
-        let results =
-            tokio::time::timeout(Duration::from_secs(2), futures::future::join_all(tasks)).await;
-
-        match results {
-            Ok(task_results) => {
-                // Log errors but don't fail on partial failures
-                for (channel_type, result) in task_results.into_iter().flatten() {
-                    if let Err(e) = result {
-                        eprintln!("Channel {} error: {}", channel_type, e);
-                        debug_context!("ChannelManager", "Channel {} error: {}", channel_type, e);
-                    } else {
-                        debug_context!("ChannelManager", "Channel {} succeeded", channel_type);
-                    }
+        if let Some(task_results) = self.join_within_budget(tasks).await {
+            for (channel_type, result) in task_results {
+                if let Err(e) = result {
+                    eprintln!("Channel {} error: {}", channel_type, e);
                 }
-                debug_context!(
-                    "ChannelManager",
-                    "send_notification_async() completed in {:?}",
-                    start.elapsed()
-                );
-                Ok(())
             }
+        }
+        Ok(())
+    }
+
+    /// How long in-process sends may take before the hook returns
+    fn dispatch_budget(&self) -> Duration {
+        Duration::from_millis(self.config.dispatch_budget_ms)
+    }
+
+    /// Wait for send tasks until the dispatch budget runs out
+    ///
+    /// Returns `None` on timeout; channels still in flight are abandoned and
+    /// listed in the debug log.
+    async fn join_within_budget(
+        &self,
+        tasks: Vec<ChannelTask>,
+    ) -> Option<Vec<(String, ChannelResult<()>)>> {
+        let budget = self.dispatch_budget();
+        let (channel_ids, mut handles): (Vec<_>, Vec<_>) = tasks.into_iter().unzip();
+
+        match tokio::time::timeout(budget, futures::future::join_all(handles.iter_mut())).await {
+            Ok(results) => Some(zip_results(channel_ids, results)),
             Err(e) => {
+                let in_flight: Vec<&String> = channel_ids
+                    .iter()
+                    .zip(&handles)
+                    .filter(|(_, handle)| !handle.is_finished())
+                    .map(|(channel_id, _)| channel_id)
+                    .collect();
                 eprintln!("Timeout waiting for channels: {}", e);
-                debug_context!("ChannelManager", "Timeout after {:?}", start.elapsed());
-                Ok(()) // Don't fail on timeout
+                debug_context!(
+                    "ChannelManager",
+                    "Dispatch budget of {:?} ran out with channels in flight: {:?}",
+                    budget,
+                    in_flight
+                );
+                None
             }
         }
     }
 
-    /// Send notification to specific channels (bypasses routing rules)
-    pub fn send_to_channels(&self, input: &HookInput, channel_ids: Vec<String>) -> Result<()> {
+    /// Deliver a notification from a detached background worker
+    ///
+    /// Unlike [`send_notification`](Self::send_notification), there is no overall
+    /// time budget: every channel runs to completion with its own timeout, and
+    /// failed channels are retried according to the `background` configuration.
+    /// When `channel_ids` is given, routing rules are bypassed.
+    pub fn deliver_in_background(
+        &self,
+        input: &HookInput,
+        channel_ids: Option<Vec<String>>,
+    ) -> Result<()> {
         let runtime = Runtime::new()?;
-        runtime.block_on(self.send_to_channels_async(input, channel_ids))
+        runtime.block_on(self.deliver_in_background_async(input, channel_ids))
     }
 
-    /// Send notification to specific channels (async version)
-    pub async fn send_to_channels_async(
+    /// Deliver a notification from a detached background worker (async version)
+    pub async fn deliver_in_background_async(
         &self,
         input: &HookInput,
-        channel_ids: Vec<String>,
+        channel_ids: Option<Vec<String>>,
     ) -> Result<()> {
-        // Deduplicate channels
-        let channel_ids = self.router.override_channels(channel_ids);
+        debug_context!("ChannelManager", "deliver_in_background_async() called");
+        let start = std::time::Instant::now();
 
-        // Create template engine with global templates
-        let template_engine = TemplateEngine::new(self.config.global_templates.clone());
+        let route = match channel_ids {
+            Some(channel_ids) => RouteDecision {
+                channels: self.router.override_channels(channel_ids),
+                severity: Severity::analyze(input),
+                ..Default::default()
+            },
+            None => self.router.route(input, &self.config)?,
+        };
+        self.cancel_escalations(input, &route);
+        let input = &with_severity(input, route.severity);
+
+        if self.is_duplicate(input) {
+            debug_context!("ChannelManager", "Duplicate notification suppressed");
+            self.record_skipped(input, &route.channels, &route.rules, DUPLICATE_REASON);
+            return Ok(());
+        }
+
+        self.defer_routed(input, &route);
+        self.register_escalations(input, &route);
+        let mut pending = self.hold_back_channels(input, &route.channels, &route.rules);
+        let settings = &self.config.background;
+        let outbox_tasks = self.spawn_due_tasks(ended_session(input), None);
+
+        for attempt in 0..=settings.max_retries {
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_millis(settings.retry_delay_ms)).await;
+                debug_context!(
+                    "ChannelManager",
+                    "Retry {} for channels: {:?}",
+                    attempt,
+                    pending
+                );
+            }
+
+            let tasks = self.spawn_channel_tasks(
+                input,
+                pending,
+                &route.rules,
+                &route.fallbacks,
+                false,
+                None,
+            );
+            let mut failed = Vec::new();
+
+            for (channel_id, result) in join_tasks(tasks).await {
+                match result {
+                    Ok(()) => debug_context!("ChannelManager", "Channel {} succeeded", channel_id),
+                    Err(e) => {
+                        eprintln!("Channel {} error: {}", channel_id, e);
+                        // A bad signature or revoked token fails the same way again
+                        if e.is_retryable() {
+                            failed.push(channel_id);
+                        }
+                    }
+                }
+            }
 
+            if failed.is_empty() {
+                break;
+            }
+            pending = failed;
+        }
+
+        log_results(join_tasks(outbox_tasks).await);
+
+        debug_context!(
+            "ChannelManager",
+            "deliver_in_background_async() completed in {:?}",
+            start.elapsed()
+        );
+        Ok(())
+    }
+
+    /// Spawn one send task per channel, skipping unknown and disabled channels
+    ///
+    /// `rules` names the routing rules that selected the channels, for the
+    /// delivery history. `rule_fallbacks` lists fallback channels contributed
+    /// by routing rules, tried after the channel's own `fallback` list.
+    /// `budget` is how long the caller waits for the tasks; rate limit delays
+    /// that do not fit collapse into the next send instead.
+    fn spawn_channel_tasks(
+        &self,
+        input: &HookInput,
+        channel_ids: Vec<String>,
+        rules: &[String],
+        rule_fallbacks: &HashMap<String, Vec<String>>,
+        warn_disabled: bool,
+        budget: Option<Duration>,
+    ) -> Vec<ChannelTask> {
         // Wrap input in Arc for safe sharing across tasks
         let input = Arc::new(input.clone());
-        let template_engine = Arc::new(template_engine);
-
+        let dispatch = Arc::new(self.dispatch());
         let mut tasks = Vec::new();
 
         for channel_id in channel_ids {
-            // Get channel configuration
-            let channel_config = self
+            if let Some((channel, channel_config)) = self.resolve_channel(&channel_id) {
+                // Skip disabled channels
+                if !channel.is_enabled(&channel_config) {
+                    if warn_disabled {
+                        eprintln!("Warning: Channel {} is not enabled", channel_id);
+                    }
+                    continue;
+                }
+
+                let fallbacks = self.resolve_fallbacks(
+                    &input,
+                    &channel_id,
+                    &channel_config,
+                    rule_fallbacks.get(&channel_id),
+                    &dispatch.template_engine,
+                );
+                let rendered =
+                    render_for_channel(&dispatch.template_engine, &input, &channel_config);
+                let delivery = self.history.delivery(&input, rules, &rendered);
+
+                // Skip channels that keep failing without spending a rate limit
+                // token, and let their fallbacks stand in
+                if dispatch.circuits.is_open(&channel_id) {
+                    debug_context!("ChannelManager", "Channel {} circuit open", channel_id);
+                    let input = Arc::clone(&input);
+                    let dispatch = Arc::clone(&dispatch);
+                    let task_channel_id = channel_id.clone();
+                    let handle = tokio::spawn(async move {
+                        skip_open_circuit(&task_channel_id, &input, fallbacks, &dispatch, &delivery)
+                            .await
+                    });
+                    tasks.push((channel_id, handle));
+                    continue;
+                }
+
+                // Apply the channel's rate limit, if configured
+                let decision = match &channel_config.rate_limit {
+                    Some(limit) => check_rate_limit(&self.state, &channel_id, limit, budget),
+                    None => RateDecision::Send { collapsed: 0 },
+                };
+                let (delay, collapsed) = match decision {
+                    RateDecision::Send { collapsed } => (None, collapsed),
+                    RateDecision::Delay(wait) => (Some(wait), 0),
+                    RateDecision::Drop | RateDecision::Collapse => {
+                        debug_context!(
+                            "ChannelManager",
+                            "Channel {} rate limited: {:?}",
+                            channel_id,
+                            decision
+                        );
+                        delivery.skip(&channel_id, None, rate_limit_reason(&decision));
+                        continue;
+                    }
+                };
+
+                let input = Arc::clone(&input);
+                let dispatch = Arc::clone(&dispatch);
+                let task_channel_id = channel_id.clone();
+
+                let handle = tokio::spawn(async move {
+                    let channel_id = task_channel_id;
+                    if let Some(wait) = delay {
+                        tokio::time::sleep(wait).await;
+                    }
+                    // The circuit may have opened while this send was delayed
+                    let Some(circuit) = dispatch.circuits.begin(&channel_id) else {
+                        return skip_open_circuit(
+                            &channel_id,
+                            &input,
+                            fallbacks,
+                            &dispatch,
+                            &delivery,
+                        )
+                        .await;
+                    };
+
+                    let attempt = delivery.begin(&channel_id, None);
+                    let result = send_with_summary(
+                        &channel_id,
+                        &channel,
+                        &input,
+                        &channel_config,
+                        &dispatch,
+                        collapsed,
+                    )
+                    .await;
+                    circuit.finish(&result);
+                    attempt.finish(&result);
+
+                    match result {
+                        Err(e) if !fallbacks.is_empty() => {
+                            send_to_fallbacks(
+                                &channel_id,
+                                e,
+                                &input,
+                                fallbacks,
+                                &dispatch,
+                                &delivery,
+                            )
+                            .await
+                        }
+                        result => result,
+                    }
+                });
+                tasks.push((channel_id, handle));
+            }
+        }
+
+        tasks
+    }
+
+    /// Deliver queued notifications and escalation steps that are due
+    ///
+    /// Deferred notifications are due once their quiet hours end, digests once
+    /// their interval has elapsed, and escalation steps once their delay has
+    /// passed without user activity. Hook invocations and the daemon do this
+    /// automatically; this entry point lets a scheduled job flush when no
+    /// hooks are firing.
+    pub fn flush_due(&self) -> Result<()> {
+        let runtime = Runtime::new()?;
+        runtime.block_on(self.flush_due_async())
+    }
+
+    /// Deliver queued notifications and escalation steps that are due (async version)
+    pub async fn flush_due_async(&self) -> Result<()> {
+        log_results(join_tasks(self.spawn_due_tasks(None, None)).await);
+        Ok(())
+    }
+
+    /// Show how the current routing rules and templates handle an input
+    /// without sending anything
+    pub fn preview(&self, input: &HookInput) -> Result<DispatchPreview> {
+        let route = self.router.route(input, &self.config)?;
+        let input = &with_severity(input, route.severity);
+        let template_engine = TemplateEngine::new(self.config.global_templates.clone());
+
+        let messages = route
+            .channels
+            .iter()
+            .map(|channel_id| {
+                let message = self
+                    .resolve_channel(channel_id)
+                    .filter(|(channel, config)| channel.is_enabled(config))
+                    .map(|(_, config)| render_for_channel(&template_engine, input, &config));
+                (channel_id.clone(), message)
+            })
+            .collect();
+
+        Ok(DispatchPreview { route, messages })
+    }
+
+    /// Send a recorded input through the current routing rules and templates
+    ///
+    /// Deduplication, quiet hours, digests and escalations are skipped so the
+    /// message goes out now; rate limits, circuits and fallbacks still apply.
+    /// Waits for every channel and returns its result.
+    pub fn replay(&self, input: &HookInput) -> Result<Vec<(String, ChannelResult<()>)>> {
+        let runtime = Runtime::new()?;
+        runtime.block_on(self.replay_async(input))
+    }
+
+    /// Send a recorded input through the current configuration (async version)
+    pub async fn replay_async(
+        &self,
+        input: &HookInput,
+    ) -> Result<Vec<(String, ChannelResult<()>)>> {
+        let route = self.router.route(input, &self.config)?;
+        let input = &with_severity(input, route.severity);
+        let tasks = self.spawn_channel_tasks(
+            input,
+            route.channels,
+            &route.rules,
+            &route.fallbacks,
+            true,
+            None,
+        );
+        Ok(join_tasks(tasks).await)
+    }
+
+    /// Resolve the fallback channels for a primary channel
+    ///
+    /// Fallbacks render with the primary's template so they deliver the same
+    /// message. Unknown, disabled and quiet channels are left out, and digest
+    /// channels get the message queued for their next digest.
+    fn resolve_fallbacks(
+        &self,
+        input: &HookInput,
+        channel_id: &str,
+        channel_config: &ChannelConfig,
+        rule_fallbacks: Option<&Vec<String>>,
+        template_engine: &TemplateEngine,
+    ) -> Vec<Fallback> {
+        let template = template_engine.get_template(
+            &input.hook_event_name,
+            channel_config.message_template.as_ref(),
+        );
+        let now = chrono::Utc::now();
+        let mut seen = HashSet::from([channel_id.to_string()]);
+        let mut fallbacks = Vec::new();
+
+        for fallback_id in channel_config
+            .fallback
+            .iter()
+            .chain(rule_fallbacks.into_iter().flatten())
+        {
+            if !seen.insert(fallback_id.clone()) {
+                continue;
+            }
+            let Some((channel, mut config)) = self.resolve_channel(fallback_id) else {
+                continue;
+            };
+            let quiet = config
+                .schedule
+                .as_ref()
+                .is_some_and(|s| !schedule::is_active(s, now));
+            let digest = config.delivery == DeliveryMode::Digest;
+            // Session end flushes a digest rather than appearing in it
+            if !channel.is_enabled(&config)
+                || quiet
+                || (digest && input.hook_event_name == HookType::SessionEnd)
+            {
+                continue;
+            }
+
+            config.message_template = Some(template.clone());
+            let digest = digest.then(|| {
+                let rendered = render_for_channel(template_engine, input, &config);
+                outbox_entry(input, fallback_id, OutboxKind::Digest, None, rendered)
+            });
+            fallbacks.push(Fallback {
+                channel_id: fallback_id.clone(),
+                channel,
+                config,
+                digest,
+            });
+        }
+
+        fallbacks
+    }
+
+    /// Circuit breaker backed by this manager's state store
+    fn circuit_breaker(&self) -> CircuitBreaker {
+        CircuitBreaker::new(self.state.clone(), self.config.circuit_breaker.clone())
+    }
+
+    /// Shared state for the send tasks of one dispatch
+    fn dispatch(&self) -> Dispatch {
+        Dispatch {
+            template_engine: TemplateEngine::new(self.config.global_templates.clone()),
+            circuits: self.circuit_breaker(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Look up a channel's configuration and its shared implementation
+    fn resolve_channel(&self, channel_id: &str) -> Option<(SharedChannel, ChannelConfig)> {
+        // Get channel configuration
+        let channel_config = self
+            .config
+            .channels
+            .get(channel_id)
+            .cloned()
+            .unwrap_or_default();
+
+        // Get the channel type from config (defaults to channel_id for backward compatibility)
+        let channel_type = if channel_config.channel_type.is_empty() {
+            channel_id.to_string()
+        } else {
+            channel_config.channel_type.clone()
+        };
+
+        // Reuse the channel instance for this channel_type
+        self.registry
+            .get_channel(&channel_type)
+            .map(|channel| (channel, channel_config))
+    }
+
+    /// Filter out channels that should not send this notification now
+    ///
+    /// Channels in their quiet hours skip or defer the notification, and
+    /// channels in digest mode queue it for their next digest.
+    fn hold_back_channels(
+        &self,
+        input: &HookInput,
+        channel_ids: &[String],
+        rules: &[String],
+    ) -> Vec<String> {
+        let now = chrono::Utc::now();
+        let mut active = Vec::new();
+
+        for channel_id in channel_ids {
+            let Some(channel_config) = self.config.channels.get(channel_id) else {
+                active.push(channel_id.clone());
+                continue;
+            };
+
+            if let Some(s) = &channel_config.schedule {
+                if !schedule::is_active(s, now) {
+                    debug_context!(
+                        "ChannelManager",
+                        "Channel {} outside active hours",
+                        channel_id
+                    );
+                    let reason = if s.outside_hours == OutsideHoursPolicy::Defer {
+                        self.queue_in_outbox(input, channel_id, OutboxKind::Deferred, None);
+                        "outside active hours, deferred to the outbox"
+                    } else {
+                        "outside active hours"
+                    };
+                    self.record_skipped(input, std::slice::from_ref(channel_id), rules, reason);
+                    continue;
+                }
+            }
+
+            if channel_config.delivery == DeliveryMode::Digest {
+                // Session end flushes the digest rather than appearing in it
+                if input.hook_event_name != HookType::SessionEnd {
+                    self.queue_in_outbox(input, channel_id, OutboxKind::Digest, None);
+                }
+                continue;
+            }
+
+            active.push(channel_id.clone());
+        }
+
+        active
+    }
+
+    /// Queue the channels deferred by routing rule schedules
+    fn defer_routed(&self, input: &HookInput, route: &RouteDecision) {
+        for (channel_id, rule) in &route.deferred {
+            debug_context!(
+                "ChannelManager",
+                "Channel {} deferred by rule {}",
+                channel_id,
+                rule
+            );
+            self.queue_in_outbox(input, channel_id, OutboxKind::Deferred, Some(rule));
+            let reason = format!(
+                "rule '{}' outside active hours, deferred to the outbox",
+                rule
+            );
+            self.record_skipped(
+                input,
+                std::slice::from_ref(channel_id),
+                &route.rules,
+                &reason,
+            );
+        }
+    }
+
+    /// Record channels that did not get a notification in the delivery history
+    fn record_skipped(
+        &self,
+        input: &HookInput,
+        channel_ids: &[String],
+        rules: &[String],
+        reason: &str,
+    ) {
+        let template_engine = TemplateEngine::new(self.config.global_templates.clone());
+        for channel_id in channel_ids {
+            let config = self
                 .config
                 .channels
-                .get(&channel_id)
+                .get(channel_id)
                 .cloned()
                 .unwrap_or_default();
+            let rendered = render_for_channel(&template_engine, input, &config);
+            self.history
+                .delivery(input, rules, &rendered)
+                .skip(channel_id, None, reason);
+        }
+    }
 
-            // Get the channel type from config (defaults to channel_id for backward compatibility)
-            let channel_type = if channel_config.channel_type.is_empty() {
-                channel_id.clone()
-            } else {
-                channel_config.channel_type.clone()
-            };
+    /// Render a notification for a channel and queue it in the outbox
+    fn queue_in_outbox(
+        &self,
+        input: &HookInput,
+        channel_id: &str,
+        kind: OutboxKind,
+        rule: Option<&str>,
+    ) {
+        let template_engine = TemplateEngine::new(self.config.global_templates.clone());
+        let message_template = self
+            .config
+            .channels
+            .get(channel_id)
+            .and_then(|c| c.message_template.as_ref());
+        let template = template_engine.get_template(&input.hook_event_name, message_template);
+        let rendered = template_engine.render(&template, input);
+        let entry = outbox_entry(input, channel_id, kind, rule, rendered);
+
+        if let Err(e) = self.state.update(|state| state.outbox.push(entry)) {
+            eprintln!(
+                "Warning: Could not queue notification for {}: {}",
+                channel_id, e
+            );
+        }
+    }
 
-            // Create a new channel instance based on channel_type
-            if let Some(channel) = self.registry.create_channel(&channel_type) {
-                // Skip disabled channels
-                if !channel.is_enabled(&channel_config) {
-                    eprintln!("Warning: Channel {} is not enabled", channel_id);
+    /// Spawn sends for everything that is due: outbox digests and escalations
+    fn spawn_due_tasks(
+        &self,
+        ended_session: Option<&str>,
+        budget: Option<Duration>,
+    ) -> Vec<ChannelTask> {
+        let mut tasks = self.spawn_outbox_tasks(ended_session);
+        tasks.extend(self.spawn_escalation_tasks(budget));
+        tasks
+    }
+
+    /// Cancel a session's pending escalations once it shows activity again
+    ///
+    /// Steps of rules the route escalates again are left to the transcript,
+    /// since the hook is a reminder of the same wait.
+    fn cancel_escalations(&self, input: &HookInput, route: &RouteDecision) {
+        let session_id = &input.common.session_id;
+        // Avoid taking the state lock when nothing is pending for the session
+        match self.state.load() {
+            Ok(state)
+                if state
+                    .escalations
+                    .iter()
+                    .any(|p| &p.session_id == session_id) => {}
+            _ => return,
+        }
+
+        let reminds: Vec<String> = route
+            .escalations
+            .iter()
+            .map(|(rule, _)| rule.clone())
+            .collect();
+        let activity = escalation::UserActivity::of(input, &reminds, chrono::Utc::now());
+        let result = self.state.update(|state| {
+            let before = state.escalations.len();
+            state
+                .escalations
+                .retain(|pending| !activity.answers(pending));
+            before - state.escalations.len()
+        });
+        match result {
+            Ok(0) => {}
+            Ok(_) => debug_context!(
+                "ChannelManager",
+                "Escalations cancelled for session {}",
+                session_id
+            ),
+            Err(e) => eprintln!("Warning: Could not cancel escalations: {}", e),
+        }
+    }
+
+    /// Record the escalation steps of the matched rules
+    ///
+    /// A rule whose chain is already running for the session is left alone,
+    /// so repeated reminders do not restart the clock.
+    fn register_escalations(&self, input: &HookInput, route: &RouteDecision) {
+        if route.escalations.is_empty() {
+            return;
+        }
+
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let session_id = &input.common.session_id;
+
+        let result = self.state.update(|state| {
+            let running: HashSet<String> = state
+                .escalations
+                .iter()
+                .filter(|pending| &pending.session_id == session_id)
+                .map(|pending| pending.rule.clone())
+                .collect();
+
+            let mut delays = Vec::new();
+            for (rule, step) in &route.escalations {
+                if running.contains(rule) {
                     continue;
                 }
+                let delay_ms = step.after_minutes as i64 * 60_000;
+                state.escalations.push(PendingEscalation {
+                    rule: rule.clone(),
+                    session_id: session_id.clone(),
+                    channels: step.channels.clone(),
+                    due_at_ms: now_ms + delay_ms,
+                    created_at_ms: now_ms,
+                    input: input.clone(),
+                });
+                delays.push(delay_ms as u64);
+            }
+            delays
+        });
 
-                let input = Arc::clone(&input);
-                let template_engine = Arc::clone(&template_engine);
+        let delays = match result {
+            Ok(delays) => delays,
+            Err(e) => {
+                eprintln!("Warning: Could not schedule escalations: {}", e);
+                return;
+            }
+        };
+        debug_context!(
+            "ChannelManager",
+            "Escalations scheduled in ms: {:?}",
+            delays
+        );
+
+        if self.flush_timers {
+            for delay_ms in delays {
+                // Wake slightly after the due time so the step is ready
+                let delay = Duration::from_millis(delay_ms + 1_000);
+                if let Err(e) = background::spawn_flush_timer(delay, self.config.debug) {
+                    eprintln!("Warning: Could not start escalation timer: {}", e);
+                }
+            }
+        }
+    }
 
-                tasks.push(tokio::spawn(async move {
-                    let result = channel
-                        .send(&input, &channel_config, &template_engine)
-                        .await;
-                    (channel_id, result)
-                }));
+    /// Take due escalation steps out of the state and spawn their sends
+    fn spawn_escalation_tasks(&self, budget: Option<Duration>) -> Vec<ChannelTask> {
+        // Avoid taking the state lock when there is nothing to escalate
+        match self.state.load() {
+            Ok(state) if !state.escalations.is_empty() => {}
+            _ => return Vec::new(),
+        }
+
+        let now = chrono::Utc::now();
+        let due = self
+            .state
+            .update(|state| escalation::take_due(&mut state.escalations, now))
+            .unwrap_or_else(|e| {
+                eprintln!("Warning: Escalation state unavailable: {}", e);
+                Vec::new()
+            });
+
+        let mut tasks = Vec::new();
+        for step in due {
+            if escalation::user_replied_since(&step.input, step.created_at_ms) {
+                debug_context!(
+                    "ChannelManager",
+                    "Escalation for session {} cancelled by user reply",
+                    step.session_id
+                );
+                continue;
             }
+
+            debug_context!(
+                "ChannelManager",
+                "Escalating rule {} to {:?}",
+                step.rule,
+                step.channels
+            );
+            let rules = std::slice::from_ref(&step.rule);
+            let channels = self.hold_back_channels(&step.input, &step.channels, rules);
+            tasks.extend(self.spawn_channel_tasks(
+                &step.input,
+                channels,
+                rules,
+                &HashMap::new(),
+                false,
+                budget,
+            ));
         }
 
-        // Wait for all tasks with timeout (2 seconds - system channel is instant, webhooks run in background)
-        if tasks.is_empty() {
-            eprintln!("Warning: No valid channels specified");
-            return Ok(());
+        tasks
+    }
+
+    /// Claim due entries in the outbox and spawn one digest send per channel
+    ///
+    /// `ended_session` flushes that session's digest entries regardless of the
+    /// digest interval. Entries are removed once their digest is sent and
+    /// released for the next flush if the send fails.
+    fn spawn_outbox_tasks(&self, ended_session: Option<&str>) -> Vec<ChannelTask> {
+        // Avoid taking the state lock when there is nothing to flush
+        match self.state.load() {
+            Ok(state) if !state.outbox.is_empty() => {}
+            _ => return Vec::new(),
         }
 
-        let results =
-            tokio::time::timeout(Duration::from_secs(2), futures::future::join_all(tasks)).await;
+        let now = chrono::Utc::now();
+        let now_ms = now.timestamp_millis();
+        let due = self
+            .state
+            .update(|state| {
+                // Digest intervals run from each channel's oldest queued entry
+                let mut oldest: HashMap<String, i64> = HashMap::new();
+                for entry in state.outbox.iter().filter(|e| e.kind == OutboxKind::Digest) {
+                    let queued_at = oldest
+                        .entry(entry.channel_id.clone())
+                        .or_insert(entry.queued_at_ms);
+                    *queued_at = (*queued_at).min(entry.queued_at_ms);
+                }
 
-        match results {
-            Ok(task_results) => {
-                for (channel_type, result) in task_results.into_iter().flatten() {
-                    if let Err(e) = result {
-                        eprintln!("Channel {} error: {}", channel_type, e);
+                let mut due = Vec::new();
+                for entry in state.outbox.iter_mut() {
+                    if !entry.is_claimed(now_ms)
+                        && self.is_outbox_entry_due(entry, now, &oldest, ended_session)
+                    {
+                        entry.claimed_at_ms = Some(now_ms);
+                        due.push(entry.clone());
                     }
                 }
-                Ok(())
+                due
+            })
+            .unwrap_or_else(|e| {
+                eprintln!("Warning: Outbox unavailable: {}", e);
+                Vec::new()
+            });
+
+        let mut by_channel: BTreeMap<String, Vec<OutboxEntry>> = BTreeMap::new();
+        for entry in due {
+            by_channel
+                .entry(entry.channel_id.clone())
+                .or_default()
+                .push(entry);
+        }
+
+        let template_engine = Arc::new(TemplateEngine::new(self.config.global_templates.clone()));
+        let mut tasks = Vec::new();
+
+        for (channel_id, entries) in by_channel {
+            let Some((channel, channel_config)) = self.resolve_channel(&channel_id) else {
+                settle_outbox(&self.state, &entries, true);
+                continue;
+            };
+            if !channel.is_enabled(&channel_config) {
+                debug_context!(
+                    "ChannelManager",
+                    "Dropping {} deferred notifications for disabled channel {}",
+                    entries.len(),
+                    channel_id
+                );
+                settle_outbox(&self.state, &entries, true);
+                continue;
             }
-            Err(e) => {
-                eprintln!("Timeout waiting for channels: {}", e);
-                Ok(())
+
+            let (title, body) = outbox::digest_message(&entries);
+            let mut digest =
+                HookInput::notification(entries[0].session_id.clone(), None, body, Some(title));
+            digest.severity = entries.iter().map(|entry| entry.severity).max();
+            let mut rules: Vec<String> = entries.iter().filter_map(|e| e.rule.clone()).collect();
+            rules.sort();
+            rules.dedup();
+            let rendered = render_for_channel(&template_engine, &digest, &channel_config);
+            let delivery = self.history.delivery(&digest, &rules, &rendered);
+            let template_engine = Arc::clone(&template_engine);
+            let task_channel_id = channel_id.clone();
+            let state = self.state.clone();
+
+            let handle = tokio::spawn(async move {
+                let attempt = delivery.begin(&task_channel_id, None);
+                let result = channel
+                    .send(&digest, &channel_config, &template_engine)
+                    .await;
+                attempt.finish(&result);
+                settle_outbox(&state, &entries, result.is_ok());
+                result
+            });
+            tasks.push((channel_id, handle));
+        }
+
+        tasks
+    }
+
+    /// Check whether a queued entry can be sent
+    ///
+    /// The channel and rule schedules must be active, and digest entries must
+    /// also have reached their channel's interval or the end of their session.
+    fn is_outbox_entry_due(
+        &self,
+        entry: &OutboxEntry,
+        now: chrono::DateTime<chrono::Utc>,
+        oldest_digest_ms: &HashMap<String, i64>,
+        ended_session: Option<&str>,
+    ) -> bool {
+        let channel_config = self.config.channels.get(&entry.channel_id);
+
+        if entry.kind == OutboxKind::Digest && ended_session != Some(entry.session_id.as_str()) {
+            let interval_ms = channel_config
+                .map(|c| c.digest_interval_ms())
+                .unwrap_or_else(|| ChannelConfig::default().digest_interval_ms());
+            let oldest = oldest_digest_ms
+                .get(&entry.channel_id)
+                .copied()
+                .unwrap_or(entry.queued_at_ms);
+            if now.timestamp_millis() - oldest < interval_ms {
+                return false;
+            }
+        }
+
+        let channel_active = channel_config
+            .and_then(|c| c.schedule.as_ref())
+            .is_none_or(|s| schedule::is_active(s, now));
+        // A rule that no longer exists cannot hold the entry back
+        channel_active
+            && entry
+                .rule
+                .as_ref()
+                .is_none_or(|name| self.router.is_rule_active(name, now))
+    }
+
+    /// Check whether an identical notification was already sent inside the dedup window
+    fn is_duplicate(&self, input: &HookInput) -> bool {
+        let settings = &self.config.dedup;
+        if !settings.enabled {
+            return false;
+        }
+
+        let template_engine = TemplateEngine::new(self.config.global_templates.clone());
+        let key = dedup::dedup_key(input, &settings.keys, &template_engine);
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let window_ms = (settings.window_secs * 1000) as i64;
+
+        self.state
+            .update(|state| dedup::check_and_record(&mut state.dedup, &key, now_ms, window_ms))
+            .unwrap_or_else(|e| {
+                eprintln!("Warning: Dedup state unavailable: {}", e);
+                false
+            })
+    }
+}
+
+/// Reason recorded for notifications suppressed by deduplication
+const DUPLICATE_REASON: &str = "duplicate inside the dedup window";
+
+/// How the current configuration would deliver an input
+#[derive(Debug, Clone, Serialize)]
+pub struct DispatchPreview {
+    /// Routing decision for the input
+    pub route: RouteDecision,
+    /// Message rendered for each routed channel; `None` for unknown or
+    /// disabled channels
+    pub messages: Vec<(String, Option<RenderedMessage>)>,
+}
+
+/// Shared state for the send tasks of one dispatch
+struct Dispatch {
+    template_engine: TemplateEngine,
+    circuits: CircuitBreaker,
+    state: StateStore,
+}
+
+/// Fallback channel, ready to take over a failed primary's message
+struct Fallback {
+    channel_id: String,
+    channel: SharedChannel,
+    config: ChannelConfig,
+    /// Entry to queue for the channel's next digest instead of sending now
+    digest: Option<OutboxEntry>,
+}
+
+/// Record a channel whose circuit is open as skipped and try its fallbacks
+async fn skip_open_circuit(
+    channel_id: &str,
+    input: &HookInput,
+    fallbacks: Vec<Fallback>,
+    dispatch: &Dispatch,
+    delivery: &Delivery,
+) -> ChannelResult<()> {
+    let error = ChannelError::CircuitOpen;
+    delivery.skip(channel_id, None, &error.to_string());
+    send_to_fallbacks(channel_id, error, input, fallbacks, dispatch, delivery).await
+}
+
+/// Try fallback channels in order after the primary channel failed
+///
+/// Returns `Ok` as soon as one fallback delivers the message, otherwise the
+/// primary channel's error. A digest fallback takes the message by queueing
+/// it. Fallbacks whose circuit is open or whose rate limit is exhausted are
+/// skipped.
+async fn send_to_fallbacks(
+    channel_id: &str,
+    error: ChannelError,
+    input: &HookInput,
+    fallbacks: Vec<Fallback>,
+    dispatch: &Dispatch,
+    delivery: &Delivery,
+) -> ChannelResult<()> {
+    for fallback in fallbacks {
+        let fallback_id = &fallback.channel_id;
+        if let Some(entry) = fallback.digest {
+            match dispatch.state.update(|state| state.outbox.push(entry)) {
+                Ok(()) => {
+                    eprintln!(
+                        "Channel {} error: {} (queued for the digest of fallback {})",
+                        channel_id, error, fallback_id
+                    );
+                    return Ok(());
+                }
+                Err(e) => {
+                    eprintln!("Fallback channel {} error: {}", fallback_id, e);
+                    continue;
+                }
+            }
+        }
+
+        if dispatch.circuits.is_open(fallback_id) {
+            let reason = ChannelError::CircuitOpen.to_string();
+            delivery.skip(fallback_id, Some(channel_id), &reason);
+            continue;
+        }
+        // A fallback sends right away, so a delay collapses into its next send
+        let collapsed = match &fallback.config.rate_limit {
+            Some(limit) => {
+                match check_rate_limit(&dispatch.state, fallback_id, limit, Some(Duration::ZERO)) {
+                    RateDecision::Send { collapsed } => collapsed,
+                    decision => {
+                        debug_context!(
+                            "ChannelManager",
+                            "Fallback channel {} rate limited: {:?}",
+                            fallback_id,
+                            decision
+                        );
+                        delivery.skip(fallback_id, Some(channel_id), rate_limit_reason(&decision));
+                        continue;
+                    }
+                }
+            }
+            None => 0,
+        };
+        let Some(circuit) = dispatch.circuits.begin(fallback_id) else {
+            let reason = ChannelError::CircuitOpen.to_string();
+            delivery.skip(fallback_id, Some(channel_id), &reason);
+            continue;
+        };
+
+        let attempt = delivery.begin(fallback_id, Some(channel_id));
+        let result = send_with_summary(
+            fallback_id,
+            &fallback.channel,
+            input,
+            &fallback.config,
+            dispatch,
+            collapsed,
+        )
+        .await;
+        circuit.finish(&result);
+        attempt.finish(&result);
+
+        match result {
+            Ok(()) => {
+                eprintln!(
+                    "Channel {} error: {} (delivered via fallback {})",
+                    channel_id, error, fallback_id
+                );
+                return Ok(());
             }
+            Err(e) => eprintln!("Fallback channel {} error: {}", fallback_id, e),
+        }
+    }
+
+    Err(error)
+}
+
+/// Send a message, then a summary of the notifications the rate limiter
+/// collapsed since the channel's last send
+///
+/// The message is delivered once its own send succeeds. A failed summary
+/// only puts its count back into the channel's bucket for the next one.
+async fn send_with_summary(
+    channel_id: &str,
+    channel: &SharedChannel,
+    input: &HookInput,
+    config: &ChannelConfig,
+    dispatch: &Dispatch,
+    collapsed: u32,
+) -> ChannelResult<()> {
+    channel
+        .send(input, config, &dispatch.template_engine)
+        .await?;
+    if collapsed == 0 {
+        return Ok(());
+    }
+
+    let summary = HookInput::notification(
+        input.common.session_id.clone(),
+        None,
+        ratelimit::collapsed_message(collapsed),
+        Some("Claude Code".to_string()),
+    );
+    if let Err(e) = channel
+        .send(&summary, config, &dispatch.template_engine)
+        .await
+    {
+        eprintln!("Channel {} summary error: {}", channel_id, e);
+        restore_collapsed(&dispatch.state, channel_id, collapsed);
+    }
+    Ok(())
+}
+
+/// Count collapsed notifications whose summary failed towards the next one
+fn restore_collapsed(state: &StateStore, channel_id: &str, collapsed: u32) {
+    let result = state.update(|state| {
+        let bucket = state.rate_limits.entry(channel_id.to_string()).or_default();
+        bucket.suppressed += collapsed;
+    });
+    if let Err(e) = result {
+        eprintln!(
+            "Warning: Rate limit state unavailable for {}: {}",
+            channel_id, e
+        );
+    }
+}
+
+/// Why the rate limiter kept a notification from a channel, for the history
+fn rate_limit_reason(decision: &RateDecision) -> &'static str {
+    match decision {
+        RateDecision::Collapse => "rate limited, collapsed into the next send",
+        _ => "rate limited, dropped",
+    }
+}
+
+/// Take a token from a channel's persisted bucket
+///
+/// If the state file cannot be accessed, the notification is sent rather
+/// than silently lost. A delay must finish within `budget`, if given.
+fn check_rate_limit(
+    state: &StateStore,
+    channel_id: &str,
+    limit: &RateLimitConfig,
+    budget: Option<Duration>,
+) -> RateDecision {
+    let max_wait_ms = budget.map(|budget| budget.as_millis() as u64);
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    state
+        .update(|state| {
+            state
+                .rate_limits
+                .entry(channel_id.to_string())
+                .or_default()
+                .acquire(limit, now_ms, max_wait_ms)
+        })
+        .unwrap_or_else(|e| {
+            eprintln!(
+                "Warning: Rate limit state unavailable for {}: {}",
+                channel_id, e
+            );
+            RateDecision::Send { collapsed: 0 }
+        })
+}
+
+/// Outbox entry for a rendered notification
+fn outbox_entry(
+    input: &HookInput,
+    channel_id: &str,
+    kind: OutboxKind,
+    rule: Option<&str>,
+    rendered: RenderedMessage,
+) -> OutboxEntry {
+    OutboxEntry {
+        channel_id: channel_id.to_string(),
+        kind,
+        rule: rule.map(|r| r.to_string()),
+        session_id: input.common.session_id.clone(),
+        cwd: input.common.cwd.clone(),
+        hook_type: input.hook_event_name,
+        category: EntryCategory::classify(input),
+        severity: input.severity.unwrap_or_default(),
+        title: rendered.title,
+        body: rendered.body,
+        queued_at_ms: chrono::Utc::now().timestamp_millis(),
+        claimed_at_ms: None,
+    }
+}
+
+/// Remove sent outbox entries, or release them for the next flush
+fn settle_outbox(state: &StateStore, entries: &[OutboxEntry], delivered: bool) {
+    if let Err(e) = state.update(|state| outbox::settle(&mut state.outbox, entries, delivered)) {
+        eprintln!("Warning: Outbox unavailable: {}", e);
+    }
+}
+
+/// Render the message a channel sends for an input
+fn render_for_channel(
+    template_engine: &TemplateEngine,
+    input: &HookInput,
+    config: &ChannelConfig,
+) -> RenderedMessage {
+    let template =
+        template_engine.get_template(&input.hook_event_name, config.message_template.as_ref());
+    template_engine.render(&template, input)
+}
+
+/// Copy of a hook input carrying the severity it is delivered with
+fn with_severity(input: &HookInput, severity: Severity) -> HookInput {
+    HookInput {
+        severity: Some(severity),
+        ..input.clone()
+    }
+}
+
+/// Session whose digests should be flushed because this input ends it
+fn ended_session(input: &HookInput) -> Option<&str> {
+    (input.hook_event_name == HookType::SessionEnd).then_some(input.common.session_id.as_str())
+}
+
+/// Send task for one channel, labelled with the channel id
+type ChannelTask = (String, JoinHandle<ChannelResult<()>>);
+
+/// Wait for all send tasks and pair each result with its channel id
+async fn join_tasks(tasks: Vec<ChannelTask>) -> Vec<(String, ChannelResult<()>)> {
+    let (channel_ids, handles): (Vec<_>, Vec<_>) = tasks.into_iter().unzip();
+    zip_results(channel_ids, futures::future::join_all(handles).await)
+}
+
+/// Pair task results with channel ids, skipping tasks that panicked
+fn zip_results(
+    channel_ids: Vec<String>,
+    results: Vec<std::result::Result<ChannelResult<()>, tokio::task::JoinError>>,
+) -> Vec<(String, ChannelResult<()>)> {
+    channel_ids
+        .into_iter()
+        .zip(results)
+        .filter_map(|(channel_id, joined)| joined.ok().map(|result| (channel_id, result)))
+        .collect()
+}
+
+/// Report the outcome of channel tasks that are not on the hook's critical path
+fn log_results(results: Vec<(String, ChannelResult<()>)>) {
+    for (channel_id, result) in results {
+        match result {
+            Ok(()) => debug_context!("ChannelManager", "Channel {} succeeded", channel_id),
+            Err(e) => eprintln!("Channel {} error: {}", channel_id, e),
         }
     }
 }
@@ -382,13 +1559,13 @@ pub fn handle_hook(input: &HookInput, sound: Option<&str>) -> Result<()> {
         HookData::Stop(_data) => {
             // Analyze transcript to generate message
             if let Some(transcript_path) = &input.common.transcript_path {
-                match analyzer::analyze_transcript(transcript_path) {
-                    Ok(status) => {
+                match input.transcript_status() {
+                    Some(status) => {
                         let title = "Claude Code";
                         let body = summary::generate_summary(transcript_path, status);
                         (title, body)
                     }
-                    Err(_) => {
+                    None => {
                         // Fall back to simple message on analysis error
                         let title = "Claude Code";
                         let body = "Claude stopped generating";
@@ -405,13 +1582,13 @@ pub fn handle_hook(input: &HookInput, sound: Option<&str>) -> Result<()> {
         HookData::SubagentStop(_data) => {
             // Analyze transcript to generate message
             if let Some(transcript_path) = &input.common.transcript_path {
-                match analyzer::analyze_transcript(transcript_path) {
-                    Ok(status) => {
+                match input.transcript_status() {
+                    Some(status) => {
                         let title = "Claude Code";
                         let body = summary::generate_summary(transcript_path, status);
                         (title, body)
                     }
-                    Err(_) => {
+                    None => {
                         // Fall back to simple message on analysis error
                         let title = "Claude Code";
                         let body = "Subagent stopped";
@@ -437,6 +1614,11 @@ pub fn handle_hook(input: &HookInput, sound: Option<&str>) -> Result<()> {
             };
             (title, body)
         }
+        HookData::SessionEnd(data) => {
+            let title = "Claude Code";
+            let body = format!("Session ended ({})", data.reason);
+            (title, body)
+        }
     };
 
     debug_context!("handle_hook", "Title: {}", title);
@@ -497,12 +1679,17 @@ pub fn parse_input() -> Result<HookInput> {
         ));
     }
 
+    parse_input_json(&input)
+}
+
+/// Parse hook input JSON, accepting the legacy notification format
+pub fn parse_input_json(input: &str) -> Result<HookInput> {
     // First try to parse as the new HookInput format
-    match serde_json::from_str::<HookInput>(&input) {
+    match serde_json::from_str::<HookInput>(input) {
         Ok(hook_input) => Ok(hook_input),
         Err(_) => {
             // If that fails, try to parse as legacy NotificationInput format
-            match serde_json::from_str::<NotificationInput>(&input) {
+            match serde_json::from_str::<NotificationInput>(input) {
                 Ok(notification_input) => {
                     // Convert legacy format to new format
                     Ok(HookInput::notification(
@@ -522,6 +1709,24 @@ pub fn parse_input() -> Result<HookInput> {
     }
 }
 
+/// Parse hook inputs captured in a file for replay
+///
+/// Accepts a single JSON object, several objects or JSON lines, and delivery
+/// history records, whose recorded input is used.
+pub fn parse_captured_inputs(content: &str) -> Result<Vec<HookInput>> {
+    let mut inputs = Vec::new();
+    for value in serde_json::Deserializer::from_str(content).into_iter::<serde_json::Value>() {
+        let value = value?;
+        if value.get("channel_id").is_some() {
+            let record: DeliveryRecord = serde_json::from_value(value)?;
+            inputs.extend(recorded_inputs(&[record]));
+        } else {
+            inputs.push(parse_input_json(&value.to_string())?);
+        }
+    }
+    Ok(inputs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -565,6 +1770,69 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_captured_inputs() {
+        let record = serde_json::json!({
+            "timestamp_ms": 0,
+            "session_id": "s2",
+            "hook_type": "Stop",
+            "channel_id": "wechat",
+            "title": "",
+            "body": "",
+            "outcome": "sent",
+            "latency_ms": 5,
+            "input": {"hook_event_name": "Stop", "session_id": "s2"}
+        });
+        let content = format!(
+            "{}\n{{\"session_id\": \"s3\", \"message\": \"Legacy\"}}\n{}",
+            r#"{"hook_event_name": "Notification", "session_id": "s1", "message": "Hi"}"#, record
+        );
+
+        let inputs = parse_captured_inputs(&content).unwrap();
+        let sessions: Vec<&str> = inputs
+            .iter()
+            .map(|input| input.common.session_id.as_str())
+            .collect();
+        assert_eq!(sessions, vec!["s1", "s3", "s2"]);
+        assert_eq!(inputs[2].hook_event_name, HookType::Stop);
+
+        assert!(parse_captured_inputs("{\"session_id\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_preview() {
+        let mut config = default_config();
+        config.routing_rules = vec![RoutingRule {
+            name: "Stops".to_string(),
+            match_conditions: RuleMatch {
+                hook_types: vec!["Stop".to_string()],
+                ..Default::default()
+            },
+            channels: vec!["system".to_string(), "missing".to_string()],
+            ..Default::default()
+        }];
+        config.channels.insert(
+            "system".to_string(),
+            ChannelConfig {
+                enabled: true,
+                message_template: Some(MessageTemplate {
+                    title: Some("{{hook_type}} in {{session_id}}".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        let manager = ChannelManager::from_config(config).unwrap();
+
+        let preview = manager
+            .preview(&HookInput::stop("s1".to_string(), None, None))
+            .unwrap();
+        assert_eq!(preview.route.rules, vec!["Stops"]);
+        assert_eq!(preview.messages.len(), 2);
+        assert_eq!(preview.messages[0].1.as_ref().unwrap().title, "Stop in s1");
+        assert!(preview.messages[1].1.is_none());
+    }
+
     #[test]
     fn test_parse_input_without_transcript_path() {
         let json = r#"{
@@ -579,6 +1847,534 @@ mod tests {
         assert_eq!(input.title, None);
     }
 
+    #[test]
+    fn test_parse_session_end_input() {
+        let json = r#"{
+            "hook_event_name": "SessionEnd",
+            "session_id": "test-session",
+            "reason": "prompt_input_exit"
+        }"#;
+
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert_eq!(input.hook_event_name, HookType::SessionEnd);
+        assert!(
+            matches!(input.data, HookData::SessionEnd(ref data) if data.reason == "prompt_input_exit")
+        );
+
+        // Stop input has no reason and still parses as Stop
+        let json = r#"{"hook_event_name": "Stop", "session_id": "test-session"}"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert!(matches!(input.data, HookData::Stop(_)));
+    }
+
+    /// Channel that records the rendered body it was asked to send
+    struct RecordingChannel {
+        fail: bool,
+        /// Fail only rate limit summaries
+        fail_summaries: bool,
+        sent: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl NotificationChannel for RecordingChannel {
+        fn channel_type(&self) -> &'static str {
+            "recording"
+        }
+
+        fn display_name(&self) -> &'static str {
+            "Recording Channel"
+        }
+
+        async fn send(
+            &self,
+            input: &HookInput,
+            config: &ChannelConfig,
+            template_engine: &TemplateEngine,
+        ) -> ChannelResult<()> {
+            if self.fail {
+                return Err(ChannelError::Timeout);
+            }
+            let template = template_engine
+                .get_template(&input.hook_event_name, config.message_template.as_ref());
+            let rendered = template_engine.render(&template, input);
+            if self.fail_summaries && rendered.body.contains("suppressed by rate limiting") {
+                return Err(ChannelError::Timeout);
+            }
+            self.sent.lock().unwrap().push(rendered.body);
+            Ok(())
+        }
+    }
+
+    /// Manager with state and history in `dir` and three test channel types:
+    /// `recording`, which records each sent body, `failing`, and
+    /// `summary-failing`, which fails only rate limit summaries
+    fn test_manager(
+        config: AppConfig,
+        dir: &std::path::Path,
+    ) -> (ChannelManager, Arc<std::sync::Mutex<Vec<String>>>) {
+        let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut manager = ChannelManager::from_config(config)
+            .unwrap()
+            .with_state_store(StateStore::new(dir.join("state.json")))
+            .with_flush_timers(false);
+        manager.history = History::new(dir.join("history.jsonl"), HistoryConfig::default());
+        for (channel_type, fail, fail_summaries) in [
+            ("recording", false, false),
+            ("failing", true, false),
+            ("summary-failing", false, true),
+        ] {
+            let sent = Arc::clone(&sent);
+            manager.registry.register_factory(channel_type, move || {
+                Box::new(RecordingChannel {
+                    fail,
+                    fail_summaries,
+                    sent: Arc::clone(&sent),
+                }) as Box<dyn NotificationChannel + Send + Sync>
+            });
+        }
+        (manager, sent)
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_delay_beyond_dispatch_budget() {
+        let mut config = default_config();
+        config.channels.insert(
+            "team".to_string(),
+            ChannelConfig {
+                enabled: true,
+                channel_type: "recording".to_string(),
+                rate_limit: Some(RateLimitConfig {
+                    max_per_minute: 1,
+                    burst: None,
+                    overflow: OverflowPolicy::Delay,
+                    max_delay_ms: 60_000,
+                }),
+                ..Default::default()
+            },
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, sent) = test_manager(config, dir.path());
+        let send = |message: &str| {
+            let input = HookInput::notification("s1".to_string(), None, message.to_string(), None);
+            let manager = &manager;
+            async move {
+                manager
+                    .send_to_channels_async(&input, vec!["team".to_string()])
+                    .await
+                    .unwrap()
+            }
+        };
+
+        send("first").await;
+        let tokens = manager.state.load().unwrap().rate_limits["team"].tokens;
+
+        // Waiting a minute does not fit the dispatch budget: the second
+        // notification collapses into the next send without spending a token
+        send("second").await;
+        let bucket = &manager.state.load().unwrap().rate_limits["team"];
+        assert_eq!(bucket.suppressed, 1);
+        assert!(bucket.tokens >= tokens);
+        assert_eq!(sent.lock().unwrap().len(), 1);
+
+        let records = manager.history.query(&HistoryFilter::default()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].outcome, DeliveryOutcome::Skipped);
+        assert_eq!(
+            records[1].reason.as_deref(),
+            Some("rate limited, collapsed into the next send")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_rule_does_not_stop_delivery() {
+        let mut config = default_config();
+        config.default_channels = vec!["team".to_string()];
+        config.channels.insert(
+            "team".to_string(),
+            ChannelConfig {
+                enabled: true,
+                channel_type: "recording".to_string(),
+                ..Default::default()
+            },
+        );
+        config.routing_rules = vec![RoutingRule {
+            name: "Broken".to_string(),
+            match_conditions: RuleMatch {
+                message_pattern: Some("(error".to_string()),
+                ..Default::default()
+            },
+            channels: vec!["team".to_string()],
+            enabled: false,
+            ..Default::default()
+        }];
+        assert!(ChannelRouter::new(&config).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, sent) = test_manager(config, dir.path());
+        let input = HookInput::notification("s1".to_string(), None, "Hello".to_string(), None);
+        manager.send_notification_async(&input).await.unwrap();
+        assert_eq!(sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_background_delivery_applies_severity() {
+        let mut config = default_config();
+        config.channels.insert(
+            "team".to_string(),
+            ChannelConfig {
+                enabled: true,
+                channel_type: "recording".to_string(),
+                message_template: Some(MessageTemplate {
+                    body: Some("{{severity}}".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        config.routing_rules = vec![RoutingRule {
+            name: "Stops are urgent".to_string(),
+            match_conditions: RuleMatch {
+                hook_types: vec!["Stop".to_string()],
+                ..Default::default()
+            },
+            channels: vec!["team".to_string()],
+            severity: Some(Severity::Critical),
+            ..Default::default()
+        }];
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, sent) = test_manager(config, dir.path());
+
+        let stop = HookInput::stop("s1".to_string(), None, None);
+        manager
+            .deliver_in_background_async(&stop, None)
+            .await
+            .unwrap();
+        let permission = HookInput::permission_request("s2".to_string(), None, None, None, None);
+        manager
+            .deliver_in_background_async(&permission, Some(vec!["team".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(*sent.lock().unwrap(), vec!["critical", "warning"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_summary_keeps_the_delivered_message() {
+        let mut config = default_config();
+        config.channels.insert(
+            "team".to_string(),
+            ChannelConfig {
+                enabled: true,
+                channel_type: "summary-failing".to_string(),
+                rate_limit: Some(RateLimitConfig {
+                    max_per_minute: 10,
+                    burst: None,
+                    overflow: OverflowPolicy::Collapse,
+                    max_delay_ms: 60_000,
+                }),
+                fallback: vec!["backup".to_string()],
+                ..Default::default()
+            },
+        );
+        config.channels.insert(
+            "backup".to_string(),
+            ChannelConfig {
+                enabled: true,
+                channel_type: "recording".to_string(),
+                ..Default::default()
+            },
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, sent) = test_manager(config, dir.path());
+        manager
+            .state
+            .update(|state| {
+                state.rate_limits.insert(
+                    "team".to_string(),
+                    TokenBucket {
+                        tokens: 10.0,
+                        updated_at_ms: chrono::Utc::now().timestamp_millis(),
+                        suppressed: 3,
+                    },
+                )
+            })
+            .unwrap();
+
+        let input = HookInput::notification("s1".to_string(), None, "Hello".to_string(), None);
+        manager
+            .send_to_channels_async(&input, vec!["team".to_string()])
+            .await
+            .unwrap();
+
+        // Sent once, not again through the fallback; the count waits for the next summary
+        assert_eq!(*sent.lock().unwrap(), vec!["Hello"]);
+        let records = manager.history.query(&HistoryFilter::default()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].outcome, DeliveryOutcome::Sent);
+        let state = manager.state.load().unwrap();
+        assert_eq!(state.rate_limits["team"].suppressed, 3);
+        assert!(state
+            .circuits
+            .get("team")
+            .is_none_or(|c| c.consecutive_failures == 0));
+    }
+
+    #[tokio::test]
+    async fn test_explicit_channels_are_deduplicated() {
+        let mut config = default_config();
+        config.dedup.enabled = true;
+        config.channels.insert(
+            "team".to_string(),
+            ChannelConfig {
+                enabled: true,
+                channel_type: "recording".to_string(),
+                ..Default::default()
+            },
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, sent) = test_manager(config, dir.path());
+        let input = HookInput::notification("s1".to_string(), None, "Hello".to_string(), None);
+
+        for _ in 0..2 {
+            manager
+                .send_to_channels_async(&input, vec!["team".to_string()])
+                .await
+                .unwrap();
+        }
+        manager
+            .deliver_in_background_async(&input, Some(vec!["team".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(sent.lock().unwrap().len(), 1);
+
+        let records = manager.history.query(&HistoryFilter::default()).unwrap();
+        let outcomes: Vec<DeliveryOutcome> = records.iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                DeliveryOutcome::Sent,
+                DeliveryOutcome::Skipped,
+                DeliveryOutcome::Skipped
+            ]
+        );
+        assert_eq!(records[1].reason.as_deref(), Some(DUPLICATE_REASON));
+    }
+
+    #[tokio::test]
+    async fn test_outbox_entries_survive_failed_sends() {
+        let config_with = |channel_type: &str| {
+            let mut config = default_config();
+            config.channels.insert(
+                "team".to_string(),
+                ChannelConfig {
+                    enabled: true,
+                    channel_type: channel_type.to_string(),
+                    ..Default::default()
+                },
+            );
+            config
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let input = HookInput::notification("s1".to_string(), None, "Hello".to_string(), None);
+
+        let (manager, _) = test_manager(config_with("failing"), dir.path());
+        manager.queue_in_outbox(&input, "team", OutboxKind::Deferred, None);
+        manager.flush_due_async().await.unwrap();
+
+        // The failed digest stays queued and unclaimed for the next flush
+        let outbox = manager.state.load().unwrap().outbox;
+        assert_eq!(outbox.len(), 1);
+        assert_eq!(outbox[0].claimed_at_ms, None);
+
+        let (manager, sent) = test_manager(config_with("recording"), dir.path());
+        manager.flush_due_async().await.unwrap();
+        assert_eq!(sent.lock().unwrap().len(), 1);
+        assert!(manager.state.load().unwrap().outbox.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_uses_fallbacks() {
+        let mut config = default_config();
+        config.circuit_breaker.failure_threshold = 1;
+        config.default_channels = vec!["team".to_string()];
+        config.channels.insert(
+            "team".to_string(),
+            ChannelConfig {
+                enabled: true,
+                channel_type: "failing".to_string(),
+                fallback: vec!["backup".to_string()],
+                ..Default::default()
+            },
+        );
+        config.channels.insert(
+            "backup".to_string(),
+            ChannelConfig {
+                enabled: true,
+                channel_type: "recording".to_string(),
+                ..Default::default()
+            },
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, sent) = test_manager(config, dir.path());
+        let input = HookInput::notification("s1".to_string(), None, "Hello".to_string(), None);
+        let replay = || async {
+            let results = manager.replay_async(&input).await.unwrap();
+            assert!(results.iter().all(|(_, result)| result.is_ok()));
+        };
+
+        // The first failure opens the circuit; the fallback delivers both times
+        replay().await;
+        replay().await;
+        assert_eq!(sent.lock().unwrap().len(), 2);
+
+        let records = manager.history.query(&HistoryFilter::default()).unwrap();
+        let attempts: Vec<(&str, DeliveryOutcome)> = records
+            .iter()
+            .map(|r| (r.channel_id.as_str(), r.outcome))
+            .collect();
+        assert_eq!(
+            attempts,
+            vec![
+                ("team", DeliveryOutcome::Failed),
+                ("backup", DeliveryOutcome::Sent),
+                ("team", DeliveryOutcome::Skipped),
+                ("backup", DeliveryOutcome::Sent),
+            ]
+        );
+        assert_eq!(
+            records[2].reason.as_deref(),
+            Some("Circuit open after repeated failures")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_to_fallbacks() {
+        let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let primary_template = ChannelConfig {
+            message_template: Some(MessageTemplate {
+                body: Some("Primary: {{message}}".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let fallback = |channel_id: &str, fail| Fallback {
+            channel_id: channel_id.to_string(),
+            channel: Arc::new(RecordingChannel {
+                fail,
+                fail_summaries: false,
+                sent: Arc::clone(&sent),
+            }),
+            config: primary_template.clone(),
+            digest: None,
+        };
+        let input = HookInput::notification("s1".to_string(), None, "Hello".to_string(), None);
+        let dir = tempfile::tempdir().unwrap();
+        let store = StateStore::new(dir.path().join("state.json"));
+        let dispatch = Dispatch {
+            template_engine: TemplateEngine::new(std::collections::HashMap::new()),
+            circuits: CircuitBreaker::new(store.clone(), CircuitBreakerConfig::default()),
+            state: store.clone(),
+        };
+        let history = History::new(dir.path().join("history.jsonl"), HistoryConfig::default());
+        let rendered = RenderedMessage {
+            title: "Claude Code".to_string(),
+            body: "Primary: Hello".to_string(),
+        };
+        let delivery = history.delivery(&input, &[], &rendered);
+
+        // The first fallback fails, the second delivers the primary's message
+        let result = send_to_fallbacks(
+            "wechat",
+            ChannelError::Timeout,
+            &input,
+            vec![fallback("broken", true), fallback("dingtalk", false)],
+            &dispatch,
+            &delivery,
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(*sent.lock().unwrap(), vec!["Primary: Hello"]);
+
+        // Only the failed fallback is tracked by the circuit breaker
+        let state = store.load().unwrap();
+        assert_eq!(state.circuits["broken"].consecutive_failures, 1);
+        assert!(!state.circuits.contains_key("dingtalk"));
+
+        // Each fallback attempt is recorded against the primary
+        let records = history.query(&HistoryFilter::default()).unwrap();
+        let attempts: Vec<(&str, DeliveryOutcome)> = records
+            .iter()
+            .map(|r| (r.channel_id.as_str(), r.outcome))
+            .collect();
+        assert_eq!(
+            attempts,
+            vec![
+                ("broken", DeliveryOutcome::Failed),
+                ("dingtalk", DeliveryOutcome::Sent)
+            ]
+        );
+        assert!(records
+            .iter()
+            .all(|r| r.fallback_for.as_deref() == Some("wechat")));
+
+        // When every fallback fails, the primary's error is reported
+        let result = send_to_fallbacks(
+            "wechat",
+            ChannelError::HttpError("503".to_string()),
+            &input,
+            vec![fallback("broken", true)],
+            &dispatch,
+            &delivery,
+        )
+        .await;
+        assert!(matches!(result, Err(ChannelError::HttpError(_))));
+
+        // A fallback out of tokens is skipped; a digest fallback queues the message
+        let mut limited = fallback("limited", false);
+        limited.config.rate_limit = Some(RateLimitConfig {
+            max_per_minute: 1,
+            burst: None,
+            overflow: OverflowPolicy::Delay,
+            max_delay_ms: 60_000,
+        });
+        store
+            .update(|state| {
+                state.rate_limits.insert(
+                    "limited".to_string(),
+                    TokenBucket {
+                        tokens: 0.0,
+                        updated_at_ms: chrono::Utc::now().timestamp_millis(),
+                        suppressed: 0,
+                    },
+                )
+            })
+            .unwrap();
+        let mut digest = fallback("digest", false);
+        digest.digest = Some(outbox_entry(
+            &input,
+            "digest",
+            OutboxKind::Digest,
+            None,
+            rendered.clone(),
+        ));
+        sent.lock().unwrap().clear();
+        let result = send_to_fallbacks(
+            "wechat",
+            ChannelError::Timeout,
+            &input,
+            vec![limited, digest],
+            &dispatch,
+            &delivery,
+        )
+        .await;
+        assert!(result.is_ok());
+        assert!(sent.lock().unwrap().is_empty());
+        let state = store.load().unwrap();
+        assert_eq!(state.rate_limits["limited"].suppressed, 1);
+        assert_eq!(state.outbox.len(), 1);
+        assert_eq!(state.outbox[0].channel_id, "digest");
+        assert_eq!(state.outbox[0].body, "Primary: Hello");
+    }
+
     #[test]
     fn test_sound_path_resolution_system() {
         // This test will only pass on macOS with system sounds available
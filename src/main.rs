@@ -6,9 +6,13 @@
 
 use clap::Parser;
 use claude_code_notifications::{
-    get_config_path, handle_hook, parse_input, start_web_server, ChannelManager, NotificationError,
+    circuit_status, forward_to_daemon, get_config_path, get_socket_path, handle_hook, load_config,
+    parse_captured_inputs, parse_input, parse_since, recorded_inputs, run_daemon,
+    spawn_detached_worker, start_web_server, AppConfig, ChannelManager, ChannelRouter, History,
+    HistoryFilter, HookInput, NotificationError, RuleOutcome, StateStore,
 };
 use std::fs;
+use std::io::Read;
 use std::path::PathBuf;
 
 // Version constants from build script
@@ -31,6 +35,7 @@ enum HookType {
     Stop,
     SubagentStop,
     PermissionRequest,
+    SessionEnd,
 }
 
 /// Command-line arguments for claude-code-notifications
@@ -43,7 +48,13 @@ enum HookType {
 
 Subcommands:
   run     - Receive JSON input and display notification (default)
+            (use --detach to deliver from a background worker and return immediately)
   init    - Configure Claude Code hooks automatically
+  ui      - Launch web UI for configuration
+  daemon  - Keep channels and config in memory and accept hook events over a Unix socket
+  status  - Show configured channels and whether failing channels are being skipped
+  history - Show recent delivery attempts and why they failed
+  replay  - Re-send recorded hook events through the current routing rules and templates
 
 JSON input format for 'run' command:
 {
@@ -80,6 +91,28 @@ enum Commands {
 
     /// Launch web UI for configuration
     Ui(UiArgs),
+
+    /// Run a long-lived notification daemon listening on a Unix socket
+    Daemon,
+
+    /// Deliver deferred notifications, digests and escalations that are due
+    Flush(FlushArgs),
+
+    /// Show channel status, including circuits opened by repeated failures
+    Status,
+
+    /// Show recorded delivery attempts
+    History(HistoryArgs),
+
+    /// Re-send recorded hook events through the current configuration
+    Replay(ReplayArgs),
+
+    /// Show how routing rules handle a hook input, without sending anything
+    Explain(ExplainArgs),
+
+    /// Deliver a notification from a detached background worker (internal)
+    #[command(hide = true)]
+    Deliver(RunArgs),
 }
 
 /// Arguments for the run command
@@ -92,6 +125,10 @@ struct RunArgs {
     /// Specific channels to use (overrides routing rules, comma-separated)
     #[arg(long, value_delimiter = ',')]
     channels: Option<Vec<String>>,
+
+    /// Hand the notification to a detached background worker and exit immediately
+    #[arg(long)]
+    detach: bool,
 }
 
 /// Arguments for the init command
@@ -118,6 +155,76 @@ struct InitArgs {
     config: Option<String>,
 }
 
+/// Arguments for the flush command
+#[derive(Parser, Debug)]
+struct FlushArgs {
+    /// Wait this long before flushing (used by escalation timers)
+    #[arg(long, hide = true)]
+    wait_ms: Option<u64>,
+}
+
+/// Arguments for the history command
+#[derive(Parser, Debug)]
+struct HistoryArgs {
+    /// Only show this session (a prefix of the session id is enough)
+    #[arg(long)]
+    session: Option<String>,
+
+    /// Only show this channel id
+    #[arg(long)]
+    channel: Option<String>,
+
+    /// Only show attempts that did not deliver
+    #[arg(long)]
+    failed: bool,
+
+    /// Only show attempts since a time: 30m, 2h, 7d, 2024-05-01 or an RFC 3339 time
+    #[arg(long)]
+    since: Option<String>,
+
+    /// Show at most this many of the most recent attempts
+    #[arg(short = 'n', long, default_value = "50")]
+    limit: usize,
+
+    /// Print records as JSON lines
+    #[arg(long)]
+    json: bool,
+}
+
+/// Arguments for the replay command
+#[derive(Parser, Debug)]
+struct ReplayArgs {
+    /// File of captured hook input JSON or history records (default: delivery history)
+    file: Option<PathBuf>,
+
+    /// Only replay this session (a prefix of the session id is enough)
+    #[arg(long)]
+    session: Option<String>,
+
+    /// Only replay events recorded since a time (delivery history only)
+    #[arg(long)]
+    since: Option<String>,
+
+    /// Replay the most recent events (default: 1 from history, all from a file)
+    #[arg(short = 'n', long)]
+    last: Option<usize>,
+
+    /// Print matched channels and rendered messages without sending
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Arguments for the explain command
+#[derive(Parser, Debug)]
+struct ExplainArgs {
+    /// File of hook input JSON or history records (default: read from stdin)
+    file: Option<PathBuf>,
+
+    /// Print the explanation as JSON lines
+    #[arg(long)]
+    json: bool,
+}
+
 /// Arguments for the ui command
 #[derive(Parser, Debug)]
 struct UiArgs {
@@ -145,11 +252,19 @@ fn main() -> Result<(), NotificationError> {
         Some(Commands::Run(run_args)) => run_command(run_args),
         Some(Commands::Init(init_args)) => init_command(init_args),
         Some(Commands::Ui(ui_args)) => ui_command(ui_args),
+        Some(Commands::Daemon) => daemon_command(),
+        Some(Commands::Flush(flush_args)) => flush_command(flush_args),
+        Some(Commands::Status) => status_command(),
+        Some(Commands::History(history_args)) => history_command(history_args),
+        Some(Commands::Replay(replay_args)) => replay_command(replay_args),
+        Some(Commands::Explain(explain_args)) => explain_command(explain_args),
+        Some(Commands::Deliver(run_args)) => deliver_command(run_args),
         None => {
             // Default to run command with sound from top-level argument
             let run_args = RunArgs {
                 sound: cli.sound,
                 channels: None,
+                detach: false,
             };
             run_command(run_args)
         }
@@ -161,19 +276,81 @@ fn run_command(args: RunArgs) -> Result<(), NotificationError> {
     // Parse JSON input from stdin
     let input = parse_input()?;
 
-    // Check if we should use the new multi-channel architecture or legacy mode
-    let config_path = get_config_path();
+    // Load configuration (if present) to decide how to deliver
+    let config = load_config_if_present()?;
 
-    if config_path.exists() {
-        // New mode: use ChannelManager with config file
-        let manager = ChannelManager::load()?;
+    // Forward to the daemon when it is running (falls back to in-process delivery)
+    if let Some(config) = &config {
+        let env = ChannelRouter::captured_env(config);
+        match forward_to_daemon(&input, args.channels.as_deref(), env) {
+            Ok(true) => return Ok(()),
+            Ok(false) => {}
+            Err(e) => eprintln!("Warning: Failed to forward to daemon: {}", e),
+        }
+    }
+
+    // Hand off to a detached worker when requested by flag or configuration
+    let background = config.as_ref().is_some_and(|c| c.background.enabled);
+    if args.detach || background {
+        let debug = config.as_ref().is_some_and(|c| c.debug);
+        match spawn_detached_worker(&input, &args.sound, args.channels.as_deref(), debug) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                eprintln!(
+                    "Warning: Failed to start background worker, delivering in-process: {}",
+                    e
+                );
+            }
+        }
+    }
+
+    deliver(&input, args, config, false)?;
+
+    // Brief pause to ensure notification is displayed (100ms is sufficient)
+    // Notifications display synchronously, but a tiny buffer ensures OS processes it
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    Ok(())
+}
+
+/// Handle the hidden deliver command - complete delivery in a detached worker
+fn deliver_command(args: RunArgs) -> Result<(), NotificationError> {
+    let input = parse_input()?;
+    deliver(&input, args, load_config_if_present()?, true)
+}
+
+/// Load the configuration file, or `None` when there is none (legacy mode)
+fn load_config_if_present() -> Result<Option<AppConfig>, NotificationError> {
+    if get_config_path().exists() {
+        Ok(Some(load_config()?))
+    } else {
+        Ok(None)
+    }
+}
 
-        // If --channels is specified, use those channels directly (bypass routing)
-        if let Some(channels) = args.channels {
-            manager.send_to_channels(&input, channels)?;
+/// Deliver a hook input in-process through configured channels or legacy mode
+///
+/// `config` is the loaded configuration file, or `None` when there is none.
+fn deliver(
+    input: &HookInput,
+    args: RunArgs,
+    config: Option<AppConfig>,
+    in_background: bool,
+) -> Result<(), NotificationError> {
+    // Check if we should use the new multi-channel architecture or legacy mode
+    if let Some(config) = config {
+        // New mode: use ChannelManager with config file
+        let manager = ChannelManager::from_loaded_config(config)?;
+
+        if in_background {
+            // Worker mode: full per-channel timeouts and retries, no overall budget
+            manager.deliver_in_background(input, args.channels)?;
+        } else if let Some(channels) = args.channels {
+            // If --channels is specified, use those channels directly (bypass routing)
+            manager.send_to_channels(input, channels)?;
         } else {
             // Otherwise, use routing rules from config
-            manager.send_notification(&input)?;
+            manager.send_notification(input)?;
         }
     } else {
         // Legacy mode: backward compatible (no config file)
@@ -183,13 +360,9 @@ fn run_command(args: RunArgs) -> Result<(), NotificationError> {
         } else {
             Some(args.sound.as_str())
         };
-        handle_hook(&input, sound_param)?;
+        handle_hook(input, sound_param)?;
     }
 
-    // Brief pause to ensure notification is displayed (100ms is sufficient)
-    // Notifications display synchronously, but a tiny buffer ensures OS processes it
-    std::thread::sleep(std::time::Duration::from_millis(100));
-
     Ok(())
 }
 
@@ -264,6 +437,7 @@ fn init_command(args: InitArgs) -> Result<(), NotificationError> {
             HookType::Stop => "Stop",
             HookType::SubagentStop => "SubagentStop",
             HookType::PermissionRequest => "PermissionRequest",
+            HookType::SessionEnd => "SessionEnd",
         };
 
         // Check if hook already exists
@@ -339,6 +513,282 @@ fn init_command(args: InitArgs) -> Result<(), NotificationError> {
     Ok(())
 }
 
+/// Handle the daemon command - serve hook events over a Unix socket
+fn daemon_command() -> Result<(), NotificationError> {
+    let socket_path = get_socket_path();
+
+    println!("Starting Claude Code Notifications daemon...");
+    println!("Config file: {:?}", get_config_path());
+    println!("Press Ctrl+C to stop the daemon");
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(run_daemon(&socket_path))
+}
+
+/// Handle the flush command - deliver due deferred notifications and escalations
+fn flush_command(args: FlushArgs) -> Result<(), NotificationError> {
+    if let Some(wait_ms) = args.wait_ms {
+        std::thread::sleep(std::time::Duration::from_millis(wait_ms));
+    }
+
+    // Load after waiting so the flush uses the latest configuration
+    let manager = ChannelManager::load()?;
+    manager.flush_due()
+}
+
+/// Handle the status command - show channels and their circuit breaker state
+fn status_command() -> Result<(), NotificationError> {
+    let config = load_config()?;
+    let state = StateStore::default().load()?;
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let circuits = circuit_status(&state.circuits, &config.circuit_breaker, now_ms);
+
+    let mut channel_ids: Vec<&String> = config.channels.keys().collect();
+    channel_ids.sort();
+
+    for channel_id in channel_ids {
+        let status = match circuits.get(channel_id) {
+            _ if !config.channels[channel_id].enabled => "disabled".to_string(),
+            Some(circuit) => match circuit.open_until_ms {
+                Some(until_ms) => format!(
+                    "circuit open for {}s ({} consecutive failures)",
+                    (until_ms - now_ms + 999) / 1000,
+                    circuit.consecutive_failures
+                ),
+                None => format!("ok ({} recent failures)", circuit.consecutive_failures),
+            },
+            None => "ok".to_string(),
+        };
+        println!("{:<20} {}", channel_id, status);
+
+        if let Some(error) = circuits.get(channel_id).and_then(|c| c.last_error.as_ref()) {
+            println!("{:<20} last error: {}", "", error);
+        }
+    }
+
+    if let Err(e) = ChannelRouter::new(&config) {
+        println!();
+        println!("{}", e);
+    }
+
+    Ok(())
+}
+
+/// Handle the history command - list recorded delivery attempts
+fn history_command(args: HistoryArgs) -> Result<(), NotificationError> {
+    let filter = HistoryFilter {
+        session_id: args.session,
+        channel_id: args.channel,
+        failed_only: args.failed,
+        since_ms: args
+            .since
+            .map(|since| parse_since(&since, chrono::Local::now()))
+            .transpose()?,
+        limit: Some(args.limit),
+    };
+    let records = History::default().query(&filter)?;
+
+    if args.json {
+        for record in &records {
+            println!("{}", serde_json::to_string(record)?);
+        }
+        return Ok(());
+    }
+
+    if records.is_empty() {
+        println!("No delivery attempts recorded");
+        return Ok(());
+    }
+
+    for record in &records {
+        let time = chrono::DateTime::from_timestamp_millis(record.timestamp_ms)
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let channel = match &record.fallback_for {
+            Some(primary) => format!("{} (fallback for {})", record.channel_id, primary),
+            None => record.channel_id.clone(),
+        };
+        println!(
+            "{}  {:<20} {:<9} {:>6}ms  {:?}  session {}",
+            time,
+            channel,
+            format!("{:?}", record.outcome).to_lowercase(),
+            record.latency_ms,
+            record.hook_type,
+            record.session_id
+        );
+        if !record.rules.is_empty() {
+            println!("    rules: {}", record.rules.join(", "));
+        }
+        if !record.title.is_empty() || !record.body.is_empty() {
+            println!("    {}: {}", record.title, record.body.replace('\n', " "));
+        }
+        if let Some(error) = &record.error {
+            println!("    error: {}", error);
+        }
+        if let Some(reason) = &record.reason {
+            println!("    reason: {}", reason);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the replay command - re-send recorded hook inputs
+fn replay_command(args: ReplayArgs) -> Result<(), NotificationError> {
+    let mut inputs: Vec<HookInput> = match &args.file {
+        Some(path) => parse_captured_inputs(&fs::read_to_string(path)?)?,
+        None => {
+            let filter = HistoryFilter {
+                since_ms: args
+                    .since
+                    .as_deref()
+                    .map(|since| parse_since(since, chrono::Local::now()))
+                    .transpose()?,
+                ..Default::default()
+            };
+            recorded_inputs(&History::default().query(&filter)?)
+        }
+    };
+
+    if let Some(session) = &args.session {
+        inputs.retain(|input| input.common.session_id.starts_with(session.as_str()));
+    }
+    let last = args
+        .last
+        .unwrap_or(if args.file.is_some() { inputs.len() } else { 1 });
+    inputs.drain(..inputs.len().saturating_sub(last));
+
+    if inputs.is_empty() {
+        println!("No hook events to replay");
+        return Ok(());
+    }
+
+    let manager = ChannelManager::load()?;
+    for input in &inputs {
+        let preview = manager.preview(input)?;
+        println!(
+            "{:?}  session {}",
+            input.hook_event_name, input.common.session_id
+        );
+        if preview.route.rules.is_empty() {
+            println!("  rules: none matched, using default channels");
+        } else {
+            println!("  rules: {}", preview.route.rules.join(", "));
+        }
+
+        if args.dry_run {
+            for (channel_id, message) in &preview.messages {
+                match message {
+                    Some(message) => println!(
+                        "  {:<20} {}: {}",
+                        channel_id,
+                        message.title,
+                        message.body.replace('\n', " ")
+                    ),
+                    None => println!("  {:<20} (unknown or disabled channel)", channel_id),
+                }
+            }
+            for (channel_id, rule) in &preview.route.deferred {
+                println!("  {:<20} (deferred by rule {})", channel_id, rule);
+            }
+            continue;
+        }
+
+        for (channel_id, result) in manager.replay(input)? {
+            match result {
+                Ok(()) => println!("  {:<20} sent", channel_id),
+                Err(e) => println!("  {:<20} error: {}", channel_id, e),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the explain command - show why each rule did or did not match
+fn explain_command(args: ExplainArgs) -> Result<(), NotificationError> {
+    let content = match &args.file {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut content = String::new();
+            std::io::stdin().read_to_string(&mut content)?;
+            content
+        }
+    };
+    let inputs = parse_captured_inputs(&content)?;
+    if inputs.is_empty() {
+        return Err(NotificationError::InvalidInput(
+            "No hook input to explain".to_string(),
+        ));
+    }
+
+    let manager = ChannelManager::load()?;
+    for input in &inputs {
+        let preview = manager.preview(input)?;
+        if args.json {
+            println!("{}", serde_json::to_string(&preview)?);
+            continue;
+        }
+
+        println!(
+            "{:?}  session {}",
+            input.hook_event_name, input.common.session_id
+        );
+        println!("  rules:");
+        if preview.route.evaluations.is_empty() {
+            println!("    (no routing rules)");
+        }
+        for evaluation in &preview.route.evaluations {
+            let (label, detail) = match &evaluation.outcome {
+                RuleOutcome::Matched { active: true } => ("matched", String::new()),
+                RuleOutcome::Matched { active: false } => {
+                    ("matched", " (outside its schedule)".to_string())
+                }
+                RuleOutcome::NotMatched { reason } => ("no match", format!(": {}", reason)),
+                RuleOutcome::Disabled => ("disabled", String::new()),
+                RuleOutcome::Skipped { reason } => ("skipped", format!(": {}", reason)),
+            };
+            println!("    {:<9} {}{}", label, evaluation.rule, detail);
+        }
+
+        println!("  severity: {}", preview.route.severity);
+
+        let channels = if preview.route.channels.is_empty() {
+            "none".to_string()
+        } else {
+            preview.route.channels.join(", ")
+        };
+        if preview.route.used_defaults {
+            println!("  channels: {} (default channels)", channels);
+        } else {
+            println!("  channels: {}", channels);
+        }
+        for (channel_id, rule) in &preview.route.deferred {
+            println!("  deferred: {} (by rule {})", channel_id, rule);
+        }
+
+        for (channel_id, message) in &preview.messages {
+            match message {
+                Some(message) => {
+                    println!("  {}:", channel_id);
+                    println!("    title: {}", message.title);
+                    for line in message.body.lines() {
+                        println!("    | {}", line);
+                    }
+                }
+                None => println!("  {}: (unknown or disabled channel)", channel_id),
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Handle the ui command - launch web UI for configuration
 fn ui_command(args: UiArgs) -> Result<(), NotificationError> {
     let config_path = get_config_path();
@@ -408,6 +858,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_run_with_detach() {
+        let cli = Cli::try_parse_from(["claude-code-notifications", "run", "--detach"]).unwrap();
+        match cli.command {
+            Some(Commands::Run(run_args)) => assert!(run_args.detach),
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_daemon_command() {
+        let cli = Cli::try_parse_from(["claude-code-notifications", "daemon"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Daemon)));
+    }
+
+    #[test]
+    fn test_flush_command() {
+        let cli = Cli::try_parse_from(["claude-code-notifications", "flush"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Flush(_))));
+
+        let cli = Cli::try_parse_from(["claude-code-notifications", "flush", "--wait-ms", "1000"])
+            .unwrap();
+        match cli.command {
+            Some(Commands::Flush(flush_args)) => assert_eq!(flush_args.wait_ms, Some(1000)),
+            _ => panic!("Expected Flush command"),
+        }
+    }
+
+    #[test]
+    fn test_status_command() {
+        let cli = Cli::try_parse_from(["claude-code-notifications", "status"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Status)));
+    }
+
+    #[test]
+    fn test_history_command() {
+        let cli = Cli::try_parse_from([
+            "claude-code-notifications",
+            "history",
+            "--channel",
+            "wechat",
+            "--failed",
+            "--since",
+            "2h",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::History(history_args)) => {
+                assert_eq!(history_args.channel.as_deref(), Some("wechat"));
+                assert!(history_args.failed);
+                assert_eq!(history_args.since.as_deref(), Some("2h"));
+                assert_eq!(history_args.limit, 50);
+            }
+            _ => panic!("Expected History command"),
+        }
+    }
+
+    #[test]
+    fn test_replay_command() {
+        let cli = Cli::try_parse_from([
+            "claude-code-notifications",
+            "replay",
+            "captured.json",
+            "--dry-run",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Replay(replay_args)) => {
+                assert_eq!(replay_args.file, Some(PathBuf::from("captured.json")));
+                assert!(replay_args.dry_run);
+                assert!(replay_args.last.is_none());
+            }
+            _ => panic!("Expected Replay command"),
+        }
+    }
+
+    #[test]
+    fn test_explain_command() {
+        let cli = Cli::try_parse_from([
+            "claude-code-notifications",
+            "explain",
+            "input.json",
+            "--json",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Explain(explain_args)) => {
+                assert_eq!(explain_args.file, Some(PathBuf::from("input.json")));
+                assert!(explain_args.json);
+            }
+            _ => panic!("Expected Explain command"),
+        }
+    }
+
     #[test]
     fn test_init_command() {
         // Test parsing init command with default values
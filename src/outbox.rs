@@ -0,0 +1,372 @@
+//! Outbox for deferred and batched notifications
+//!
+//! Notifications are held in the outbox instead of being sent immediately in
+//! two cases: they arrived during a channel's quiet hours and were deferred,
+//! or the channel uses `digest` delivery. Entries are rendered with the
+//! channel's template, kept in the runtime state, and later delivered as a
+//! single digest message per channel.
+//!
+//! Entries stay in the outbox until their digest is sent. While a send is in
+//! flight they are claimed so other hook processes leave them alone; a failed
+//! send releases the claim, and a claim left behind by a process that exited
+//! mid-send expires after [`CLAIM_TIMEOUT_MS`].
+
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::analyzer::Status;
+use crate::hooks::{HookData, HookInput, HookType};
+use crate::severity::Severity;
+
+/// How long a claimed entry is left alone before another flush retries it
+pub const CLAIM_TIMEOUT_MS: i64 = 5 * 60 * 1000;
+
+/// Why a notification is waiting in the outbox
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxKind {
+    /// Deferred by quiet hours; sent when the schedule is active again
+    #[default]
+    Deferred,
+    /// Batched by digest delivery; sent after the interval or at session end
+    Digest,
+}
+
+/// What a notification tells the user, used to summarize digests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryCategory {
+    /// Claude finished a task
+    TaskCompleted,
+    /// Claude is waiting for an answer or plan approval
+    QuestionPending,
+    /// Claude is waiting for a permission decision
+    PermissionRequest,
+    /// A subagent finished
+    SubagentFinished,
+    /// Anything else
+    #[default]
+    Other,
+}
+
+impl EntryCategory {
+    /// Classify a hook input
+    ///
+    /// The hook type comes from `hook_event_name`, as `data` is parsed by
+    /// shape (see [`Severity::of`]).
+    pub fn classify(input: &HookInput) -> Self {
+        match input.hook_event_name {
+            HookType::Stop => match input.transcript_status() {
+                Some(Status::Question) | Some(Status::PlanReady) => Self::QuestionPending,
+                Some(Status::SessionLimitReached) | Some(Status::APIError) => Self::Other,
+                _ => Self::TaskCompleted,
+            },
+            HookType::SubagentStop => Self::SubagentFinished,
+            HookType::PermissionRequest => Self::PermissionRequest,
+            HookType::PreToolUse => match &input.data {
+                HookData::PreToolUse(data)
+                    if matches!(data.tool_name.as_str(), "AskUserQuestion" | "ExitPlanMode") =>
+                {
+                    Self::QuestionPending
+                }
+                _ => Self::Other,
+            },
+            HookType::Notification => match &input.data {
+                HookData::Notification(data)
+                    if data.notification_type.as_deref() == Some("permission_prompt")
+                        || data.message.to_lowercase().contains("permission") =>
+                {
+                    Self::PermissionRequest
+                }
+                _ => Self::QuestionPending,
+            },
+            HookType::SessionEnd => Self::Other,
+        }
+    }
+
+    /// Describe a count of entries in this category
+    fn describe(self, count: usize) -> String {
+        let (one, many) = match self {
+            Self::TaskCompleted => ("task completed", "tasks completed"),
+            Self::QuestionPending => ("question pending", "questions pending"),
+            Self::PermissionRequest => ("permission request", "permission requests"),
+            Self::SubagentFinished => ("subagent finished", "subagents finished"),
+            Self::Other => ("other notification", "other notifications"),
+        };
+        format!("{} {}", count, if count == 1 { one } else { many })
+    }
+}
+
+/// A rendered notification waiting in the outbox
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OutboxEntry {
+    /// Channel that will receive the notification
+    pub channel_id: String,
+    /// Why the notification is waiting
+    #[serde(default)]
+    pub kind: OutboxKind,
+    /// Routing rule whose schedule deferred the notification, if any
+    #[serde(default)]
+    pub rule: Option<String>,
+    /// Claude session identifier
+    pub session_id: String,
+    /// Working directory of the session
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Hook event that produced the notification
+    pub hook_type: HookType,
+    /// What the notification tells the user
+    #[serde(default)]
+    pub category: EntryCategory,
+    /// Severity the notification was routed with
+    #[serde(default)]
+    pub severity: Severity,
+    /// Rendered notification title
+    pub title: String,
+    /// Rendered notification body
+    pub body: String,
+    /// Time the notification was queued (ms since epoch)
+    pub queued_at_ms: i64,
+    /// Time a flush started sending the entry (ms since epoch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_at_ms: Option<i64>,
+}
+
+impl OutboxEntry {
+    /// Whether a send of this entry may still be in flight
+    pub fn is_claimed(&self, now_ms: i64) -> bool {
+        self.claimed_at_ms
+            .is_some_and(|claimed_at_ms| now_ms - claimed_at_ms < CLAIM_TIMEOUT_MS)
+    }
+}
+
+/// Settle claimed entries once their digest send finished
+///
+/// Delivered entries leave the outbox; otherwise their claim is released so
+/// the next flush retries them.
+pub fn settle(outbox: &mut Vec<OutboxEntry>, claimed: &[OutboxEntry], delivered: bool) {
+    if delivered {
+        outbox.retain(|entry| !claimed.contains(entry));
+    } else {
+        for entry in outbox.iter_mut().filter(|entry| claimed.contains(entry)) {
+            entry.claimed_at_ms = None;
+        }
+    }
+}
+
+/// Build the digest title and body for a channel's queued notifications
+///
+/// The body starts with a summary line such as "3 tasks completed, 1 question
+/// pending", followed by the notifications grouped by session.
+pub fn digest_message(entries: &[OutboxEntry]) -> (String, String) {
+    let title = if entries.len() == 1 {
+        "Claude Code: 1 notification".to_string()
+    } else {
+        format!("Claude Code: {} notifications", entries.len())
+    };
+
+    let mut sections = vec![summarize(entries.iter())];
+
+    // Group by session, keeping sessions in order of their first notification
+    let mut sessions: Vec<(&str, Vec<&OutboxEntry>)> = Vec::new();
+    for entry in entries {
+        match sessions.iter_mut().find(|(id, _)| *id == entry.session_id) {
+            Some((_, group)) => group.push(entry),
+            None => sessions.push((&entry.session_id, vec![entry])),
+        }
+    }
+
+    for (session_id, group) in sessions {
+        let mut lines = vec![format!(
+            "{}: {}",
+            session_label(session_id, group[0].cwd.as_deref()),
+            summarize(group.iter().copied())
+        )];
+        for entry in group {
+            let time = Local
+                .timestamp_millis_opt(entry.queued_at_ms)
+                .single()
+                .map(|t| t.format("%H:%M").to_string())
+                .unwrap_or_default();
+            lines.push(format!("- {} {}: {}", time, entry.title, entry.body));
+        }
+        sections.push(lines.join("\n"));
+    }
+
+    (title, sections.join("\n\n"))
+}
+
+/// Count entries per category, e.g. "3 tasks completed, 1 question pending"
+fn summarize<'a>(entries: impl Iterator<Item = &'a OutboxEntry>) -> String {
+    let mut counts: BTreeMap<EntryCategory, usize> = BTreeMap::new();
+    for entry in entries {
+        *counts.entry(entry.category).or_default() += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(category, count)| category.describe(count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Short label for a session: its project directory name, or its id prefix
+fn session_label(session_id: &str, cwd: Option<&str>) -> String {
+    let short_id: String = session_id.chars().take(8).collect();
+    match cwd
+        .and_then(|cwd| std::path::Path::new(cwd).file_name())
+        .and_then(|name| name.to_str())
+    {
+        Some(project) => format!("{} ({})", project, short_id),
+        None => format!("Session {}", short_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(session_id: &str, category: EntryCategory, body: &str) -> OutboxEntry {
+        OutboxEntry {
+            channel_id: "dingtalk".to_string(),
+            kind: OutboxKind::Digest,
+            rule: None,
+            session_id: session_id.to_string(),
+            cwd: Some("/home/dev/my-project".to_string()),
+            hook_type: HookType::Stop,
+            category,
+            severity: Severity::Notice,
+            title: "Claude Code".to_string(),
+            body: body.to_string(),
+            queued_at_ms: 0,
+            claimed_at_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_settle_claimed_entries() {
+        let mut claimed = entry("s", EntryCategory::TaskCompleted, "claimed");
+        claimed.claimed_at_ms = Some(1_000);
+        let waiting = entry("s", EntryCategory::TaskCompleted, "waiting");
+        let mut outbox = vec![claimed.clone(), waiting.clone()];
+
+        assert!(claimed.is_claimed(2_000));
+        assert!(!claimed.is_claimed(1_000 + CLAIM_TIMEOUT_MS));
+        assert!(!waiting.is_claimed(2_000));
+
+        // A failed send releases the claim
+        settle(&mut outbox, std::slice::from_ref(&claimed), false);
+        assert_eq!(outbox.len(), 2);
+        assert!(outbox.iter().all(|entry| entry.claimed_at_ms.is_none()));
+
+        // A delivered send removes only the claimed entries
+        outbox[0].claimed_at_ms = Some(1_000);
+        settle(&mut outbox, &[claimed], true);
+        assert_eq!(outbox, vec![waiting]);
+    }
+
+    #[test]
+    fn test_digest_message() {
+        let (title, body) = digest_message(&[
+            entry(
+                "session-a-1234",
+                EntryCategory::TaskCompleted,
+                "Fixed tests",
+            ),
+            entry(
+                "session-b-5678",
+                EntryCategory::QuestionPending,
+                "Which DB?",
+            ),
+            entry("session-a-1234", EntryCategory::TaskCompleted, "Added docs"),
+            entry("session-a-1234", EntryCategory::PermissionRequest, "Bash"),
+        ]);
+
+        assert_eq!(title, "Claude Code: 4 notifications");
+
+        let mut sections = body.split("\n\n");
+        assert_eq!(
+            sections.next().unwrap(),
+            "2 tasks completed, 1 question pending, 1 permission request"
+        );
+
+        let session_a = sections.next().unwrap();
+        assert!(
+            session_a.starts_with("my-project (session-): 2 tasks completed, 1 permission request")
+        );
+        assert_eq!(session_a.lines().count(), 4);
+        assert!(session_a.contains("Claude Code: Fixed tests"));
+
+        let session_b = sections.next().unwrap();
+        assert!(session_b.contains("Which DB?"));
+        assert!(sections.next().is_none());
+    }
+
+    #[test]
+    fn test_classify() {
+        let classify = |json: serde_json::Value| {
+            EntryCategory::classify(&serde_json::from_value::<HookInput>(json).unwrap())
+        };
+
+        assert_eq!(
+            classify(serde_json::json!({
+                "hook_event_name": "Stop",
+                "session_id": "s",
+                "stop_hook_active": false
+            })),
+            EntryCategory::TaskCompleted
+        );
+        assert_eq!(
+            classify(serde_json::json!({
+                "hook_event_name": "SubagentStop",
+                "session_id": "s",
+                "stop_hook_active": false
+            })),
+            EntryCategory::SubagentFinished
+        );
+        assert_eq!(
+            classify(serde_json::json!({
+                "hook_event_name": "PermissionRequest",
+                "session_id": "s",
+                "tool_name": "Bash",
+                "tool_input": {"command": "rm -rf build"}
+            })),
+            EntryCategory::PermissionRequest
+        );
+        assert_eq!(
+            classify(serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "s",
+                "tool_name": "AskUserQuestion",
+                "tool_input": {}
+            })),
+            EntryCategory::QuestionPending
+        );
+        assert_eq!(
+            classify(serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "s",
+                "tool_name": "Bash",
+                "tool_input": {"command": "ls"}
+            })),
+            EntryCategory::Other
+        );
+        assert_eq!(
+            classify(serde_json::json!({
+                "hook_event_name": "Notification",
+                "session_id": "s",
+                "message": "Claude needs your permission to use Bash"
+            })),
+            EntryCategory::PermissionRequest
+        );
+        assert_eq!(
+            classify(serde_json::json!({
+                "hook_event_name": "Notification",
+                "session_id": "s",
+                "message": "Claude is waiting for your input"
+            })),
+            EntryCategory::QuestionPending
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Glob and regex patterns for matching paths in routing rules
+//!
+//! Patterns are globs by default: `*` matches within one path component, `**`
+//! across components, `?` one character and `{a,b}` either alternative. A
+//! trailing `/**` also matches the directory itself, so
+//! `~/work/client-a/**` covers the repository and everything below it.
+//! Prefix a pattern with `re:` to use a regular expression instead, which
+//! matches anywhere unless anchored. A leading `~` (after `^` in a regex)
+//! expands to the home directory in both forms.
+
+use regex::Regex;
+
+/// Compiled glob or regex pattern
+#[derive(Debug, Clone)]
+pub struct PathPattern {
+    regex: Regex,
+}
+
+impl PathPattern {
+    /// Compile a pattern, returning a description of what is wrong with it
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let regex_source = match pattern.strip_prefix("re:") {
+            Some(source) => {
+                let (anchor, source) = match source.strip_prefix('^') {
+                    Some(rest) => ("^", rest),
+                    None => ("", source),
+                };
+                let (home, rest) = split_home(source);
+                format!("{}{}{}", anchor, home, rest)
+            }
+            None => {
+                let (home, rest) = split_home(pattern);
+                glob_to_regex(&home, rest)?
+            }
+        };
+        let regex = compile_regex(&regex_source)?;
+        Ok(Self { regex })
+    }
+
+    /// Check whether a path or name matches, ignoring a trailing slash
+    pub fn is_match(&self, text: &str) -> bool {
+        let text = match text.strip_suffix('/') {
+            Some(trimmed) if !trimmed.is_empty() => trimmed,
+            _ => text,
+        };
+        self.regex.is_match(text)
+    }
+}
+
+/// Compile a regex, describing a syntax error on one line with its column
+///
+/// The regex crate reports syntax errors over several lines, with a caret
+/// under the offending part of the pattern, so the pattern is parsed again
+/// with regex-syntax for the error's kind and span.
+pub fn compile_regex(source: &str) -> Result<Regex, String> {
+    Regex::new(source).map_err(|e| {
+        let located = match regex_syntax::Parser::new().parse(source) {
+            Err(regex_syntax::Error::Parse(e)) => Some((e.kind().to_string(), *e.span())),
+            Err(regex_syntax::Error::Translate(e)) => Some((e.kind().to_string(), *e.span())),
+            _ => None,
+        };
+        match located {
+            Some((message, span)) if span.start.line > 1 => format!(
+                "{} at line {}, column {}",
+                message, span.start.line, span.start.column
+            ),
+            Some((message, span)) => format!("{} at column {}", message, span.start.column),
+            None => e
+                .to_string()
+                .lines()
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    })
+}
+
+/// Split off a leading `~`, returning the home directory as an escaped regex
+fn split_home(source: &str) -> (String, &str) {
+    match source.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => (regex::escape(&home.to_string_lossy()), rest),
+            None => (String::new(), source),
+        },
+        _ => (String::new(), source),
+    }
+}
+
+/// Translate a glob into an anchored regex, after an already escaped prefix
+fn glob_to_regex(prefix: &str, glob: &str) -> Result<String, String> {
+    let glob = glob.strip_suffix('/').unwrap_or(glob);
+    let (body, any_depth) = match glob.strip_suffix("/**") {
+        Some(body) => (body, true),
+        None => (glob, false),
+    };
+
+    let mut regex = format!("^{}", prefix);
+    let mut chars = body.chars().peekable();
+    let mut in_braces = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches no directories at all
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '{' if !in_braces => {
+                in_braces = true;
+                regex.push_str("(?:");
+            }
+            '}' if in_braces => {
+                in_braces = false;
+                regex.push(')');
+            }
+            ',' if in_braces => regex.push('|'),
+            '[' => {
+                let mut class = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    class.push(c);
+                }
+                if !closed || class.is_empty() {
+                    return Err(format!("Empty or unterminated '[' in glob '{}'", glob));
+                }
+                let class = match class.strip_prefix('!') {
+                    Some(negated) => format!("^{}", negated),
+                    None => class,
+                };
+                regex.push('[');
+                regex.push_str(&class.replace('\\', "\\\\"));
+                regex.push(']');
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    if in_braces {
+        return Err(format!("Unterminated '{{' in glob '{}'", glob));
+    }
+    if any_depth {
+        regex.push_str("(?:/.*)?");
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        PathPattern::parse(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_glob_patterns() {
+        assert!(matches("/work/client-a/**", "/work/client-a"));
+        assert!(matches("/work/client-a/**", "/work/client-a/api/src/"));
+        assert!(!matches("/work/client-a/**", "/work/client-ab"));
+        assert!(matches("/work/*/api", "/work/client-a/api"));
+        assert!(!matches("/work/*/api", "/work/a/b/api"));
+        assert!(matches("/work/**/api", "/work/api"));
+        assert!(matches("/work/**/api", "/work/a/b/api"));
+        assert!(matches("/work/{client-a,client-b}/**", "/work/client-b/x"));
+        assert!(matches("client-?", "client-a"));
+        assert!(matches("v[0-9].[!a-z]", "v1.0"));
+        assert!(!matches("v[0-9].[!a-z]", "v1.x"));
+        assert!(matches("a+b (1)", "a+b (1)"));
+    }
+
+    #[test]
+    fn test_regex_patterns() {
+        assert!(matches("re:client-(a|b)", "/work/client-b/api"));
+        assert!(!matches("re:^client-(a|b)$", "/work/client-b/api"));
+        assert!(PathPattern::parse("re:(").is_err());
+    }
+
+    #[test]
+    fn test_home_expansion() {
+        let home = dirs::home_dir().unwrap();
+        let project = home.join("personal").join("blog");
+
+        assert!(matches("~/personal/**", project.to_str().unwrap()));
+        assert!(matches("re:^~/personal/", project.to_str().unwrap()));
+    }
+
+    #[test]
+    fn test_regex_errors() {
+        assert_eq!(
+            compile_regex("(abc").unwrap_err(),
+            "unclosed group at column 1"
+        );
+        assert_eq!(
+            compile_regex("a[z-a]").unwrap_err(),
+            "invalid character class range, the start must be <= the end at column 3"
+        );
+        assert_eq!(
+            compile_regex(r"a\p{Nope}").unwrap_err(),
+            "Unicode property not found at column 2"
+        );
+        assert_eq!(
+            compile_regex("(?x)a\n(b").unwrap_err(),
+            "unclosed group at line 2, column 1"
+        );
+    }
+
+    #[test]
+    fn test_invalid_globs() {
+        assert!(PathPattern::parse("/work/{a,b").is_err());
+        assert!(PathPattern::parse("/work/[").is_err());
+        assert!(PathPattern::parse("/work/[abc").is_err());
+    }
+}
@@ -0,0 +1,230 @@
+//! Per-channel rate limiting with token buckets
+//!
+//! Each channel with a `rate_limit` configuration gets a token bucket that is
+//! persisted in the runtime state, so limits hold across short-lived hook
+//! processes. When the bucket is empty, the channel's overflow policy decides
+//! whether the notification is delayed, dropped, or collapsed into a single
+//! "N more notifications" message.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::config::{OverflowPolicy, RateLimitConfig};
+
+/// Token bucket state for a single channel
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TokenBucket {
+    /// Available tokens (may go negative while delayed sends are queued)
+    pub tokens: f64,
+    /// Last refill time in milliseconds since the Unix epoch
+    pub updated_at_ms: i64,
+    /// Notifications collapsed since the last successful send
+    pub suppressed: u32,
+}
+
+/// Outcome of asking the rate limiter for permission to send
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateDecision {
+    /// Send now; `collapsed` notifications were suppressed and should be summarized
+    Send { collapsed: u32 },
+    /// Send after waiting for the given duration
+    Delay(Duration),
+    /// Discard the notification
+    Drop,
+    /// Discard the notification but count it for the next summary
+    Collapse,
+}
+
+impl TokenBucket {
+    /// Refill the bucket for the elapsed time and try to take a token
+    ///
+    /// `max_wait_ms` is how long the caller can wait for a delayed send. A
+    /// delay within `max_delay_ms` that the caller cannot wait for collapses
+    /// the notification into the next summary instead, without reserving a
+    /// token.
+    pub fn acquire(
+        &mut self,
+        limit: &RateLimitConfig,
+        now_ms: i64,
+        max_wait_ms: Option<u64>,
+    ) -> RateDecision {
+        let capacity = limit.capacity();
+        let rate_per_ms = limit.max_per_minute as f64 / 60_000.0;
+
+        if self.updated_at_ms == 0 {
+            // First use: start with a full bucket
+            self.tokens = capacity;
+        } else {
+            let elapsed_ms = (now_ms - self.updated_at_ms).max(0) as f64;
+            self.tokens = (self.tokens + elapsed_ms * rate_per_ms).min(capacity);
+        }
+        self.updated_at_ms = now_ms;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+
+            // Use a second token for the summary if one is available
+            let collapsed = if self.suppressed > 0 && self.tokens >= 1.0 {
+                self.tokens -= 1.0;
+                std::mem::take(&mut self.suppressed)
+            } else {
+                0
+            };
+            return RateDecision::Send { collapsed };
+        }
+
+        match limit.overflow {
+            OverflowPolicy::Delay if rate_per_ms > 0.0 => {
+                let wait_ms = ((1.0 - self.tokens) / rate_per_ms).ceil() as u64;
+                if wait_ms > limit.max_delay_ms {
+                    RateDecision::Drop
+                } else if max_wait_ms.is_some_and(|max_wait_ms| wait_ms >= max_wait_ms) {
+                    self.suppressed += 1;
+                    RateDecision::Collapse
+                } else {
+                    // Reserve the token now so concurrent hooks queue behind us
+                    self.tokens -= 1.0;
+                    RateDecision::Delay(Duration::from_millis(wait_ms))
+                }
+            }
+            OverflowPolicy::Collapse => {
+                self.suppressed += 1;
+                RateDecision::Collapse
+            }
+            _ => RateDecision::Drop,
+        }
+    }
+}
+
+/// Build the summary body for collapsed notifications
+pub fn collapsed_message(count: u32) -> String {
+    if count == 1 {
+        "1 more notification was suppressed by rate limiting".to_string()
+    } else {
+        format!(
+            "{} more notifications were suppressed by rate limiting",
+            count
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(overflow: OverflowPolicy) -> RateLimitConfig {
+        RateLimitConfig {
+            max_per_minute: 2,
+            burst: None,
+            overflow,
+            max_delay_ms: 60_000,
+        }
+    }
+
+    #[test]
+    fn test_burst_then_drop() {
+        let limit = limit(OverflowPolicy::Drop);
+        let mut bucket = TokenBucket::default();
+
+        assert_eq!(
+            bucket.acquire(&limit, 1_000, None),
+            RateDecision::Send { collapsed: 0 }
+        );
+        assert_eq!(
+            bucket.acquire(&limit, 1_000, None),
+            RateDecision::Send { collapsed: 0 }
+        );
+        assert_eq!(bucket.acquire(&limit, 1_000, None), RateDecision::Drop);
+    }
+
+    #[test]
+    fn test_refill_over_time() {
+        let limit = limit(OverflowPolicy::Drop);
+        let mut bucket = TokenBucket::default();
+
+        bucket.acquire(&limit, 1_000, None);
+        bucket.acquire(&limit, 1_000, None);
+        assert_eq!(bucket.acquire(&limit, 1_000, None), RateDecision::Drop);
+
+        // Two per minute: one token every 30 seconds
+        assert_eq!(
+            bucket.acquire(&limit, 31_000, None),
+            RateDecision::Send { collapsed: 0 }
+        );
+    }
+
+    #[test]
+    fn test_delay_reserves_token() {
+        let limit = limit(OverflowPolicy::Delay);
+        let mut bucket = TokenBucket::default();
+
+        bucket.acquire(&limit, 1_000, None);
+        bucket.acquire(&limit, 1_000, None);
+        assert_eq!(
+            bucket.acquire(&limit, 1_000, None),
+            RateDecision::Delay(Duration::from_millis(30_000))
+        );
+        assert_eq!(
+            bucket.acquire(&limit, 1_000, None),
+            RateDecision::Delay(Duration::from_millis(60_000))
+        );
+    }
+
+    #[test]
+    fn test_delay_longer_than_caller_can_wait() {
+        let limit = limit(OverflowPolicy::Delay);
+        let mut bucket = TokenBucket::default();
+
+        bucket.acquire(&limit, 1_000, None);
+        bucket.acquire(&limit, 1_000, None);
+        let tokens = bucket.tokens;
+
+        // The wait does not fit: collapse without reserving a token
+        assert_eq!(
+            bucket.acquire(&limit, 1_000, Some(2_000)),
+            RateDecision::Collapse
+        );
+        assert_eq!(bucket.tokens, tokens);
+        assert_eq!(
+            bucket.acquire(&limit, 31_000, Some(2_000)),
+            RateDecision::Send { collapsed: 0 }
+        );
+
+        // It is summarized once a second token is available
+        assert_eq!(
+            bucket.acquire(&limit, 91_000, Some(2_000)),
+            RateDecision::Send { collapsed: 1 }
+        );
+    }
+
+    #[test]
+    fn test_collapse_counts_suppressed() {
+        let limit = limit(OverflowPolicy::Collapse);
+        let mut bucket = TokenBucket::default();
+
+        bucket.acquire(&limit, 1_000, None);
+        bucket.acquire(&limit, 1_000, None);
+        assert_eq!(bucket.acquire(&limit, 1_000, None), RateDecision::Collapse);
+        assert_eq!(bucket.acquire(&limit, 1_000, None), RateDecision::Collapse);
+
+        // After a full refill, the next send carries the collapsed count
+        assert_eq!(
+            bucket.acquire(&limit, 61_000, None),
+            RateDecision::Send { collapsed: 2 }
+        );
+        assert_eq!(bucket.suppressed, 0);
+    }
+
+    #[test]
+    fn test_collapsed_message() {
+        assert_eq!(
+            collapsed_message(1),
+            "1 more notification was suppressed by rate limiting"
+        );
+        assert_eq!(
+            collapsed_message(3),
+            "3 more notifications were suppressed by rate limiting"
+        );
+    }
+}
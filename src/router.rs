@@ -4,23 +4,371 @@
 //! channels should receive a notification based on hook type, message content,
 //! and custom routing rules.
 
-use crate::config::{AppConfig, RoutingRule};
+use crate::analyzer::Status;
+use crate::config::{AppConfig, EscalationStep, OutsideHoursPolicy, RoutingRule};
 use crate::error::{NotificationError, Result};
+use crate::expr::Expr;
 use crate::hooks::{HookData, HookInput};
+use crate::jsonpath::JsonPath;
+use crate::pattern::{compile_regex, PathPattern};
+use crate::schedule::Schedule;
+use crate::severity::Severity;
+use chrono::{DateTime, Utc};
 use regex::Regex;
-use std::collections::HashSet;
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::{Cell, OnceCell};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
 
 /// Channel router for intelligent notification distribution
 pub struct ChannelRouter {
-    rules: Vec<RoutingRule>,
+    rules: Vec<CompiledRule>,
+    /// Whether any rule expression reads the transcript `status`
+    needs_status: bool,
+}
+
+/// Routing rule with its patterns and expression compiled
+struct CompiledRule {
+    rule: RoutingRule,
+    message_pattern: Option<Regex>,
+    tool_pattern: Option<Regex>,
+    expression: Option<Expr>,
+    tool_input: Vec<(JsonPath, ValuePattern)>,
+    session_pattern: Option<Regex>,
+    hostname: Option<PathPattern>,
+    env: Vec<(String, Option<PathPattern>)>,
+    time: Option<Schedule>,
+    cwd_pattern: Option<PathPattern>,
+    project: Option<PathPattern>,
+    schedule: Option<Schedule>,
+}
+
+/// Pattern a tool_input value is matched against
+///
+/// A `regex` is matched against the value as is; only a `glob` gets the
+/// path treatment of [`PathPattern`].
+enum ValuePattern {
+    Regex(Regex),
+    Glob(PathPattern),
+}
+
+impl ValuePattern {
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            ValuePattern::Regex(regex) => regex.is_match(text),
+            ValuePattern::Glob(glob) => glob.is_match(text),
+        }
+    }
+}
+
+impl CompiledRule {
+    /// Compile a rule's patterns and expression, collecting every problem
+    ///
+    /// `position` is the rule's 1-based position in `routing_rules`.
+    fn compile(position: usize, rule: &RoutingRule) -> std::result::Result<Self, Vec<String>> {
+        let conditions = &rule.match_conditions;
+        let mut problems = Problems {
+            position,
+            rule,
+            found: Vec::new(),
+        };
+        let regex = |pattern: &Option<String>| pattern.as_deref().map(compile_regex).transpose();
+        let path_pattern =
+            |pattern: &Option<String>| pattern.as_deref().map(PathPattern::parse).transpose();
+        let schedule_problem = |e: NotificationError| match e {
+            NotificationError::ConfigError(message) => message,
+            e => e.to_string(),
+        };
+
+        let message_pattern = problems.check("message_pattern", regex(&conditions.message_pattern));
+        let tool_pattern = problems.check("tool_pattern", regex(&conditions.tool_pattern));
+
+        let mut tool_input = Vec::new();
+        for condition in &conditions.tool_input {
+            let path = problems.check("tool_input path", JsonPath::parse(&condition.path));
+            let pattern = match (&condition.regex, &condition.glob) {
+                (Some(regex), None) => compile_regex(regex).map(ValuePattern::Regex),
+                (None, Some(glob)) => PathPattern::parse(glob).map(ValuePattern::Glob),
+                _ => Err(format!(
+                    "set exactly one of 'regex' or 'glob' for '{}'",
+                    condition.path
+                )),
+            };
+            if let (Some(path), Some(pattern)) = (path, problems.check("tool_input", pattern)) {
+                tool_input.push((path, pattern));
+            }
+        }
+
+        for name in &conditions.status {
+            if !Status::ALL.iter().any(|s| format!("{:?}", s) == *name) {
+                problems.check::<()>("status", Err(format!("unknown status '{}'", name)));
+            }
+        }
+
+        let session_pattern = problems.check("session_pattern", regex(&conditions.session_pattern));
+        let hostname = problems.check("hostname", path_pattern(&conditions.hostname));
+        let mut env: Vec<_> = conditions
+            .env
+            .iter()
+            .filter_map(|(name, pattern)| {
+                let pattern =
+                    problems.check(&format!("env pattern for {}", name), path_pattern(pattern))?;
+                Some((name.clone(), pattern))
+            })
+            .collect();
+        env.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let time = if conditions.time_window.is_empty()
+            && conditions.weekdays.is_empty()
+            && conditions.timezone.is_none()
+        {
+            None
+        } else {
+            let schedule = Schedule::from_parts(
+                &conditions.weekdays,
+                &conditions.time_window,
+                conditions.timezone.as_deref(),
+            )
+            .map_err(schedule_problem);
+            problems.check("time condition", schedule)
+        };
+
+        let cwd_pattern = problems.check("cwd_pattern", path_pattern(&conditions.cwd_pattern));
+        let project = problems.check("project", path_pattern(&conditions.project));
+        let expression = problems.check(
+            "expression",
+            conditions
+                .expression
+                .as_deref()
+                .map(Expr::parse)
+                .transpose()
+                .map_err(|e| e.to_string()),
+        );
+        let schedule = problems.check(
+            "schedule",
+            rule.schedule
+                .as_ref()
+                .map(Schedule::parse)
+                .transpose()
+                .map_err(schedule_problem),
+        );
+
+        if !problems.found.is_empty() {
+            return Err(problems.found);
+        }
+        Ok(Self {
+            rule: rule.clone(),
+            message_pattern: message_pattern.flatten(),
+            tool_pattern: tool_pattern.flatten(),
+            tool_input,
+            session_pattern: session_pattern.flatten(),
+            hostname: hostname.flatten(),
+            env,
+            time,
+            cwd_pattern: cwd_pattern.flatten(),
+            project: project.flatten(),
+            expression: expression.flatten(),
+            schedule: schedule.flatten(),
+        })
+    }
+}
+
+/// Problems found while compiling one rule
+struct Problems<'a> {
+    position: usize,
+    rule: &'a RoutingRule,
+    found: Vec<String>,
+}
+
+impl Problems<'_> {
+    /// Keep a compiled value, or note why it failed to compile
+    fn check<T>(&mut self, what: &str, result: std::result::Result<T, String>) -> Option<T> {
+        result
+            .map_err(|e| {
+                self.found.push(format!(
+                    "Invalid {} in rule #{} '{}': {}",
+                    what, self.position, self.rule.name, e
+                ))
+            })
+            .ok()
+    }
+}
+
+/// Hook input being routed, with derived fields computed on first use
+struct RuleInput<'a> {
+    input: &'a HookInput,
+    now: DateTime<Utc>,
+    project: OnceCell<Option<String>>,
+    severity: Cell<Option<Severity>>,
+    fields: OnceCell<Value>,
+}
+
+impl<'a> RuleInput<'a> {
+    fn new(input: &'a HookInput, now: DateTime<Utc>) -> Self {
+        Self {
+            input,
+            now,
+            project: OnceCell::new(),
+            severity: Cell::new(input.severity),
+            fields: OnceCell::new(),
+        }
+    }
+
+    fn project(&self) -> Option<&str> {
+        self.project
+            .get_or_init(|| self.input.common.project())
+            .as_deref()
+    }
+
+    /// Transcript status of a Stop or SubagentStop hook
+    fn status(&self) -> Option<Status> {
+        self.input.transcript_status()
+    }
+
+    /// Severity as set by the rules matched so far
+    ///
+    /// Starts from the severity the input carries, or the default for its
+    /// hook type and transcript status.
+    fn severity(&self) -> Severity {
+        if let Some(severity) = self.severity.get() {
+            return severity;
+        }
+        let severity = Severity::of(self.input, || self.status());
+        self.severity.set(Some(severity));
+        severity
+    }
+}
+
+/// Hostname of this machine, looked up once per process
+fn hostname() -> &'static str {
+    static HOSTNAME: OnceLock<String> = OnceLock::new();
+    HOSTNAME.get_or_init(|| gethostname::gethostname().to_string_lossy().into_owned())
+}
+
+/// Result of routing a hook input
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RouteDecision {
+    /// Names of the rules that matched, in rule order
+    pub rules: Vec<String>,
+    /// How each rule fared, in evaluation order
+    pub evaluations: Vec<RuleEvaluation>,
+    /// Whether no rule added channels, so `default_channels` were used
+    pub used_defaults: bool,
+    /// Severity to deliver with, after the matched rules' overrides
+    pub severity: Severity,
+    /// Channels that should receive the notification now
+    pub channels: Vec<String>,
+    /// Channels deferred by a rule's schedule, with the rule name
+    pub deferred: Vec<(String, String)>,
+    /// Escalation steps of the matched rules, with the rule name
+    pub escalations: Vec<(String, EscalationStep)>,
+    /// Fallback channels contributed by matched rules, keyed by channel id
+    pub fallbacks: HashMap<String, Vec<String>>,
+}
+
+/// How a routing rule fared against a hook input
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleEvaluation {
+    /// Rule name
+    pub rule: String,
+    #[serde(flatten)]
+    pub outcome: RuleOutcome,
+}
+
+/// Outcome of evaluating one routing rule
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum RuleOutcome {
+    /// Every condition held; `active` is false outside the rule's schedule
+    Matched { active: bool },
+    /// A condition did not hold
+    NotMatched { reason: String },
+    /// The rule is disabled
+    Disabled,
+    /// The rule was not evaluated
+    Skipped { reason: String },
 }
 
 impl ChannelRouter {
     /// Create a new channel router from configuration
-    pub fn new(config: &AppConfig) -> Self {
-        Self {
-            rules: config.routing_rules.clone(),
+    ///
+    /// Rule patterns and expressions are compiled here, so problems are
+    /// reported when the configuration is checked or saved rather than when a
+    /// hook fires. The error lists every invalid condition of every rule, one
+    /// per line, with the rule's position and name.
+    pub fn new(config: &AppConfig) -> Result<Self> {
+        let (router, problems) = Self::compile(config);
+        if !problems.is_empty() {
+            return Err(NotificationError::RoutingError(problems.join("\n")));
+        }
+        Ok(router)
+    }
+
+    /// Create a channel router for delivering a hook, skipping invalid rules
+    ///
+    /// A broken rule must not stop every notification, so it is left out
+    /// with a warning and the remaining rules route as usual.
+    pub fn lenient(config: &AppConfig) -> Self {
+        let (router, problems) = Self::compile(config);
+        for problem in problems {
+            eprintln!("Warning: Skipping routing rule: {}", problem);
+        }
+        router
+    }
+
+    /// Values of the environment variables that routing rules read
+    ///
+    /// A hook forwarded to the daemon sends these along, so `env`
+    /// conditions see the hook's environment rather than the daemon's.
+    pub fn captured_env(config: &AppConfig) -> HashMap<String, String> {
+        config
+            .routing_rules
+            .iter()
+            .flat_map(|rule| rule.match_conditions.env.keys())
+            .filter_map(|name| {
+                let value = std::env::var_os(name)?;
+                Some((name.clone(), value.to_string_lossy().into_owned()))
+            })
+            .collect()
+    }
+
+    /// Compile every valid rule, returning the problems of the others
+    fn compile(config: &AppConfig) -> (Self, Vec<String>) {
+        let mut rules = Vec::new();
+        let mut problems = Vec::new();
+        for (index, rule) in config.routing_rules.iter().enumerate() {
+            match CompiledRule::compile(index + 1, rule) {
+                Ok(compiled) => rules.push(compiled),
+                Err(found) => problems.extend(found),
+            }
         }
+        // Stable, so rules of equal priority keep their order
+        rules.sort_by_key(|compiled| std::cmp::Reverse(compiled.rule.priority));
+
+        let needs_status = rules
+            .iter()
+            .filter_map(|compiled| compiled.expression.as_ref())
+            .any(|expression| expression.references("status"));
+
+        (
+            Self {
+                rules,
+                needs_status,
+            },
+            problems,
+        )
+    }
+
+    /// Check whether a routing rule's schedule is active
+    ///
+    /// A rule without a schedule, or one that no longer exists, is always active.
+    pub fn is_rule_active(&self, name: &str, now: DateTime<Utc>) -> bool {
+        self.rules
+            .iter()
+            .find(|compiled| compiled.rule.name == name)
+            .and_then(|compiled| compiled.schedule.as_ref())
+            .is_none_or(|schedule| schedule.is_active_at(now))
     }
 
     /// Find which channels should receive this notification
@@ -28,68 +376,417 @@ impl ChannelRouter {
     /// Returns a list of channel IDs based on matching routing rules.
     /// If no rules match, returns the default channels from configuration.
     pub fn match_channels(&self, input: &HookInput, config: &AppConfig) -> Result<Vec<String>> {
-        let mut matched_channels = Vec::new();
+        Ok(self.route(input, config)?.channels)
+    }
+
+    /// Route a hook input, applying rule schedules at the current time
+    pub fn route(&self, input: &HookInput, config: &AppConfig) -> Result<RouteDecision> {
+        self.route_at(input, config, Utc::now())
+    }
+
+    /// Route a hook input, applying rule schedules at a specific time
+    ///
+    /// Rules are evaluated by priority, and `default` rules only when no
+    /// other rule matched. A matched rule's `severity` applies to the rules
+    /// after it and to the decision. A matching rule outside its active hours still
+    /// counts as a match, so default channels are not used in its place. Its
+    /// channels are skipped or deferred according to the schedule's policy.
+    /// Excluded channels are removed last, from every rule's channels.
+    pub fn route_at(
+        &self,
+        input: &HookInput,
+        config: &AppConfig,
+        now: DateTime<Utc>,
+    ) -> Result<RouteDecision> {
+        let mut decision = RouteDecision::default();
+        let mut excluded = HashSet::new();
+        let rule_input = RuleInput::new(input, now);
 
-        // Check each routing rule in order
-        for rule in &self.rules {
-            if !rule.enabled {
+        let mut any_matched =
+            self.apply_rules(&rule_input, false, now, &mut decision, &mut excluded);
+        if !any_matched {
+            any_matched = self.apply_rules(&rule_input, true, now, &mut decision, &mut excluded);
+        } else {
+            for compiled in self.rules.iter().filter(|compiled| compiled.rule.default) {
+                let outcome = if compiled.rule.enabled {
+                    RuleOutcome::Skipped {
+                        reason: "a more specific rule matched".to_string(),
+                    }
+                } else {
+                    RuleOutcome::Disabled
+                };
+                decision.evaluations.push(RuleEvaluation {
+                    rule: compiled.rule.name.clone(),
+                    outcome,
+                });
+            }
+        }
+
+        // If no rules matched, use default channels
+        if !any_matched {
+            decision.channels = config.default_channels.clone();
+            decision.used_defaults = true;
+        }
+
+        decision
+            .channels
+            .retain(|channel_id| !excluded.contains(channel_id));
+        decision
+            .deferred
+            .retain(|(channel_id, _)| !excluded.contains(channel_id));
+        decision.fallbacks.retain(|channel_id, fallbacks| {
+            fallbacks.retain(|fallback| !excluded.contains(fallback));
+            !excluded.contains(channel_id) && !fallbacks.is_empty()
+        });
+
+        decision.severity = rule_input.severity();
+
+        // A channel sent to now does not need a deferred copy
+        let mut seen = HashSet::new();
+        decision.deferred.retain(|(channel_id, _)| {
+            !decision.channels.contains(channel_id) && seen.insert(channel_id.clone())
+        });
+
+        Ok(decision)
+    }
+
+    /// Apply the matching regular or default rules to a decision
+    ///
+    /// Returns whether a rule matched. A rule with no channels still counts,
+    /// so it silences the input, unless it only excludes channels; those
+    /// leave the default channels in place.
+    fn apply_rules(
+        &self,
+        rule_input: &RuleInput,
+        defaults: bool,
+        now: DateTime<Utc>,
+        decision: &mut RouteDecision,
+        excluded: &mut HashSet<String>,
+    ) -> bool {
+        let mut any_matched = false;
+        let mut stopped_by: Option<&str> = None;
+
+        for compiled in &self.rules {
+            let rule = &compiled.rule;
+            if rule.default != defaults {
                 continue;
             }
+            let outcome = if !rule.enabled {
+                RuleOutcome::Disabled
+            } else if let Some(stopper) = stopped_by {
+                RuleOutcome::Skipped {
+                    reason: format!("rule '{}' stopped evaluation", stopper),
+                }
+            } else if let Some(reason) = self.unmet_condition(rule_input, compiled) {
+                RuleOutcome::NotMatched { reason }
+            } else {
+                if let Some(severity) = rule.severity {
+                    rule_input.severity.set(Some(severity));
+                }
+                let active = self.apply_rule(compiled, now, decision, excluded);
+                any_matched |= !rule.channels.is_empty() || rule.exclude_channels.is_empty();
+                if rule.stop {
+                    stopped_by = Some(&rule.name);
+                }
+                RuleOutcome::Matched { active }
+            };
+            decision.evaluations.push(RuleEvaluation {
+                rule: rule.name.clone(),
+                outcome,
+            });
+        }
+
+        any_matched
+    }
+
+    /// Add a matched rule's channels, exclusions and escalations to a decision
+    ///
+    /// Returns whether the rule's schedule is active.
+    fn apply_rule(
+        &self,
+        compiled: &CompiledRule,
+        now: DateTime<Utc>,
+        decision: &mut RouteDecision,
+        excluded: &mut HashSet<String>,
+    ) -> bool {
+        let rule = &compiled.rule;
+        decision.rules.push(rule.name.clone());
+        excluded.extend(rule.exclude_channels.iter().cloned());
 
-            if self.matches_rule(input, rule)? {
-                // Add channels from this rule (avoid duplicates)
+        let active = compiled
+            .schedule
+            .as_ref()
+            .is_none_or(|schedule| schedule.is_active_at(now));
+        if !active {
+            let defer = rule
+                .schedule
+                .as_ref()
+                .is_some_and(|config| config.outside_hours == OutsideHoursPolicy::Defer);
+            if defer {
                 for channel_id in &rule.channels {
-                    if !matched_channels.contains(channel_id) {
-                        matched_channels.push(channel_id.clone());
-                    }
+                    decision
+                        .deferred
+                        .push((channel_id.clone(), rule.name.clone()));
+                }
+            }
+        } else {
+            // Add channels from this rule (avoid duplicates)
+            for channel_id in &rule.channels {
+                if !decision.channels.contains(channel_id) {
+                    decision.channels.push(channel_id.clone());
                 }
             }
-        }
 
-        // If no rules matched, use default channels
-        if matched_channels.is_empty() {
-            matched_channels = config.default_channels.clone();
+            for step in &rule.escalation {
+                decision.escalations.push((rule.name.clone(), step.clone()));
+            }
+
+            if !rule.fallback.is_empty() {
+                for channel_id in &rule.channels {
+                    decision
+                        .fallbacks
+                        .entry(channel_id.clone())
+                        .or_default()
+                        .extend(rule.fallback.iter().cloned());
+                }
+            }
         }
 
-        Ok(matched_channels)
+        active
     }
 
-    /// Check if a hook input matches a routing rule
-    fn matches_rule(&self, input: &HookInput, rule: &RoutingRule) -> Result<bool> {
+    /// Find the first condition of a rule that the hook input fails
+    ///
+    /// Returns `None` when every condition holds, or a description of the
+    /// failed condition for `explain`.
+    fn unmet_condition(&self, rule_input: &RuleInput, compiled: &CompiledRule) -> Option<String> {
+        let input = rule_input.input;
+        let conditions = &compiled.rule.match_conditions;
+
         // Check hook type match
-        if !rule.match_conditions.hook_types.is_empty() {
+        if !conditions.hook_types.is_empty() {
             let hook_type_str = format!("{:?}", input.hook_event_name);
-            if !rule.match_conditions.hook_types.contains(&hook_type_str) {
-                return Ok(false);
+            if !conditions.hook_types.contains(&hook_type_str) {
+                return Some(format!(
+                    "hook type {} is not one of {}",
+                    hook_type_str,
+                    conditions.hook_types.join(", ")
+                ));
             }
         }
 
         // Check message pattern
-        if let Some(pattern) = &rule.match_conditions.message_pattern {
-            let regex = Regex::new(pattern)
-                .map_err(|e| NotificationError::RoutingError(format!("Invalid regex: {}", e)))?;
+        if let Some(regex) = &compiled.message_pattern {
             let message = self.extract_message(input);
             if !regex.is_match(&message) {
-                return Ok(false);
+                return Some(format!(
+                    "message \"{}\" does not match message_pattern /{}/",
+                    message, regex
+                ));
             }
         }
 
         // Check tool pattern for PreToolUse
-        if let Some(pattern) = &rule.match_conditions.tool_pattern {
+        if let Some(regex) = &compiled.tool_pattern {
             if let HookData::PreToolUse(data) = &input.data {
-                let regex = Regex::new(pattern).map_err(|e| {
-                    NotificationError::RoutingError(format!("Invalid regex: {}", e))
-                })?;
                 if !regex.is_match(&data.tool_name) {
-                    return Ok(false);
+                    return Some(format!(
+                        "tool {} does not match tool_pattern /{}/",
+                        data.tool_name, regex
+                    ));
                 }
             } else {
                 // Tool pattern specified but not a PreToolUse hook
-                return Ok(false);
+                return Some("tool_pattern only matches PreToolUse hooks".to_string());
+            }
+        }
+
+        // Check tool input fields for PreToolUse
+        if !compiled.tool_input.is_empty() {
+            let HookData::PreToolUse(data) = &input.data else {
+                return Some("tool_input only matches PreToolUse hooks".to_string());
+            };
+            let tool_input = data.tool_input.as_ref().unwrap_or(&Value::Null);
+            for ((path, pattern), condition) in
+                compiled.tool_input.iter().zip(&conditions.tool_input)
+            {
+                let matched = path
+                    .select(tool_input)
+                    .into_iter()
+                    .any(|value| match value {
+                        Value::String(text) => pattern.is_match(text),
+                        Value::Null => false,
+                        value => pattern.is_match(&value.to_string()),
+                    });
+                if !matched {
+                    return Some(format!(
+                        "no tool_input value at {} matches {}",
+                        condition.path,
+                        condition
+                            .regex
+                            .as_ref()
+                            .or(condition.glob.as_ref())
+                            .map_or("", |p| p)
+                    ));
+                }
+            }
+        }
+
+        // Check transcript status for Stop and SubagentStop
+        if !conditions.status.is_empty() {
+            let status = rule_input.status().map(|status| format!("{:?}", status));
+            if !status
+                .as_ref()
+                .is_some_and(|status| conditions.status.contains(status))
+            {
+                return Some(format!(
+                    "status {} is not one of {}",
+                    status.as_deref().unwrap_or("(none)"),
+                    conditions.status.join(", ")
+                ));
+            }
+        }
+
+        // Check severity
+        if let Some(min_severity) = conditions.min_severity {
+            let severity = rule_input.severity();
+            if severity < min_severity {
+                return Some(format!(
+                    "severity {} is below min_severity {}",
+                    severity, min_severity
+                ));
+            }
+        }
+
+        // Check permission mode and session
+        if !conditions.permission_modes.is_empty() {
+            let mode = input.common.permission_mode.as_ref();
+            if !mode.is_some_and(|mode| conditions.permission_modes.contains(mode)) {
+                return Some(format!(
+                    "permission mode {} is not one of {}",
+                    mode.map_or("(none)", |mode| mode.as_str()),
+                    conditions.permission_modes.join(", ")
+                ));
+            }
+        }
+        if let Some(regex) = &compiled.session_pattern {
+            if !regex.is_match(&input.common.session_id) {
+                return Some(format!(
+                    "session {} does not match session_pattern /{}/",
+                    input.common.session_id, regex
+                ));
+            }
+        }
+
+        // Check the machine and environment
+        if let Some(pattern) = &compiled.hostname {
+            if !pattern.is_match(hostname()) {
+                return Some(format!(
+                    "hostname {} does not match {}",
+                    hostname(),
+                    conditions.hostname.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+        for (name, pattern) in &compiled.env {
+            let value = rule_input.input.env_var(name);
+            match (pattern, &value) {
+                (None, Some(_)) => return Some(format!("environment variable {} is set", name)),
+                (Some(_), None) => {
+                    return Some(format!("environment variable {} is not set", name))
+                }
+                (Some(pattern), Some(value)) if !pattern.is_match(value) => {
+                    return Some(format!(
+                        "environment variable {}={} does not match {}",
+                        name,
+                        value,
+                        conditions.env[name].as_deref().unwrap_or_default()
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        // Check the day and time
+        if let Some(time) = &compiled.time {
+            if !time.is_active_at(rule_input.now) {
+                let window: Vec<&str> = conditions
+                    .weekdays
+                    .iter()
+                    .chain(&conditions.time_window)
+                    .chain(&conditions.timezone)
+                    .map(String::as_str)
+                    .collect();
+                return Some(format!(
+                    "{} is outside the rule's time window ({})",
+                    rule_input
+                        .now
+                        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    window.join(" ")
+                ));
             }
         }
 
-        Ok(true)
+        // Check working directory and project
+        if let Some(pattern) = &compiled.cwd_pattern {
+            let cwd = input.common.cwd.as_deref();
+            if !cwd.is_some_and(|cwd| pattern.is_match(cwd)) {
+                return Some(format!(
+                    "cwd {} does not match cwd_pattern {}",
+                    cwd.unwrap_or("(none)"),
+                    conditions.cwd_pattern.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+        if let Some(pattern) = &compiled.project {
+            let project = rule_input.project();
+            if !project.is_some_and(|p| pattern.is_match(p)) {
+                return Some(format!(
+                    "project {} does not match {}",
+                    project.unwrap_or("(none)"),
+                    conditions.project.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+
+        if let Some(expression) = &compiled.expression {
+            let fields = rule_input
+                .fields
+                .get_or_init(|| self.expression_context(rule_input));
+            if !expression.evaluate(fields) {
+                return Some("expression is false".to_string());
+            }
+        }
+
+        None
+    }
+
+    /// Fields of a hook input as seen by rule expressions
+    ///
+    /// Besides the input's own fields this adds `hook` (the hook type),
+    /// `message` for hooks without one (the text `message_pattern` matches),
+    /// `project`, `hostname`, and `status`, the transcript analysis of Stop
+    /// and SubagentStop hooks, when an expression reads it.
+    fn expression_context(&self, rule_input: &RuleInput) -> Value {
+        let input = rule_input.input;
+        let mut context = serde_json::to_value(input).unwrap_or_default();
+        if let Value::Object(fields) = &mut context {
+            fields.insert(
+                "hook".to_string(),
+                Value::String(format!("{:?}", input.hook_event_name)),
+            );
+            fields
+                .entry("message")
+                .or_insert_with(|| Value::String(self.extract_message(input)));
+            fields.insert("project".to_string(), rule_input.project().into());
+            fields.insert("hostname".to_string(), hostname().into());
+
+            if self.needs_status {
+                let status = rule_input.status().map(|status| format!("{:?}", status));
+                fields.insert("status".to_string(), status.into());
+            }
+        }
+        context
     }
 
     /// Extract message text from hook input for pattern matching
@@ -99,6 +796,7 @@ impl ChannelRouter {
             HookData::PreToolUse(data) => data.tool_name.clone(),
             HookData::Stop(_data) => "Claude stopped".to_string(),
             HookData::SubagentStop(_data) => "Subagent stopped".to_string(),
+            HookData::SessionEnd(data) => format!("Session ended ({})", data.reason),
             HookData::PermissionRequest(data) => {
                 if let Some(desc) = &data.description {
                     desc.clone()
@@ -127,7 +825,7 @@ impl ChannelRouter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{ChannelConfig, RuleMatch};
+    use crate::config::{ChannelConfig, RuleMatch, ToolInputMatch};
     use std::collections::HashMap;
 
     fn create_test_config() -> AppConfig {
@@ -147,13 +845,18 @@ mod tests {
             routing_rules: vec![],
             global_templates: HashMap::new(),
             debug: false,
+            background: Default::default(),
+            dedup: Default::default(),
+            circuit_breaker: Default::default(),
+            history: Default::default(),
+            dispatch_budget_ms: 2000,
         }
     }
 
     #[test]
     fn test_match_default_channels() {
         let config = create_test_config();
-        let router = ChannelRouter::new(&config);
+        let router = ChannelRouter::new(&config).unwrap();
 
         let input = HookInput::notification(
             "test".to_string(),
@@ -176,10 +879,10 @@ mod tests {
                 ..Default::default()
             },
             channels: vec!["system".to_string()],
-            enabled: true,
+            ..Default::default()
         }];
 
-        let router = ChannelRouter::new(&config);
+        let router = ChannelRouter::new(&config).unwrap();
 
         let stop_input = HookInput::stop("test".to_string(), None, Some("Test stop".to_string()));
 
@@ -208,12 +911,25 @@ mod tests {
                 hook_types: vec![],
                 message_pattern: Some(".*error.*".to_string()),
                 tool_pattern: None,
+                expression: None,
+                tool_input: vec![],
+                status: vec![],
+                min_severity: None,
+                permission_modes: vec![],
+                session_pattern: None,
+                hostname: None,
+                env: HashMap::new(),
+                time_window: vec![],
+                weekdays: vec![],
+                timezone: None,
+                cwd_pattern: None,
+                project: None,
             },
             channels: vec!["system".to_string()],
-            enabled: true,
+            ..Default::default()
         }];
 
-        let router = ChannelRouter::new(&config);
+        let router = ChannelRouter::new(&config).unwrap();
 
         let error_input = HookInput::notification(
             "test".to_string(),
@@ -247,12 +963,25 @@ mod tests {
                 hook_types: vec![],
                 message_pattern: None,
                 tool_pattern: Some("ExitPlanMode".to_string()),
+                expression: None,
+                tool_input: vec![],
+                status: vec![],
+                min_severity: None,
+                permission_modes: vec![],
+                session_pattern: None,
+                hostname: None,
+                env: HashMap::new(),
+                time_window: vec![],
+                weekdays: vec![],
+                timezone: None,
+                cwd_pattern: None,
+                project: None,
             },
             channels: vec!["system".to_string()],
-            enabled: true,
+            ..Default::default()
         }];
 
-        let router = ChannelRouter::new(&config);
+        let router = ChannelRouter::new(&config).unwrap();
 
         let exit_plan_input = HookInput::pre_tool_use(
             "test".to_string(),
@@ -280,7 +1009,7 @@ mod tests {
     #[test]
     fn test_override_channels() {
         let config = create_test_config();
-        let router = ChannelRouter::new(&config);
+        let router = ChannelRouter::new(&config).unwrap();
 
         let channels = router.override_channels(vec![
             "system".to_string(),
@@ -290,6 +1019,743 @@ mod tests {
         assert_eq!(channels, vec!["system", "wechat"]);
     }
 
+    #[test]
+    fn test_rule_schedule() {
+        use crate::config::ScheduleConfig;
+        use chrono::TimeZone;
+
+        let mut config = create_test_config();
+        config.routing_rules = vec![RoutingRule {
+            name: "Office hours".to_string(),
+            channels: vec!["dingtalk".to_string()],
+            schedule: Some(ScheduleConfig {
+                hours: vec!["09:00-18:00".to_string()],
+                timezone: Some("UTC".to_string()),
+                outside_hours: OutsideHoursPolicy::Defer,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }];
+
+        let router = ChannelRouter::new(&config).unwrap();
+        let input = HookInput::stop("test".to_string(), None, None);
+
+        let day = Utc.with_ymd_and_hms(2024, 1, 3, 10, 0, 0).unwrap();
+        let decision = router.route_at(&input, &config, day).unwrap();
+        assert_eq!(decision.channels, vec!["dingtalk"]);
+        assert!(decision.deferred.is_empty());
+
+        // At night the rule still matches, so defaults are not used
+        let night = Utc.with_ymd_and_hms(2024, 1, 3, 3, 0, 0).unwrap();
+        let decision = router.route_at(&input, &config, night).unwrap();
+        assert!(decision.channels.is_empty());
+        assert_eq!(
+            decision.deferred,
+            vec![("dingtalk".to_string(), "Office hours".to_string())]
+        );
+        assert!(!router.is_rule_active("Office hours", night));
+        assert!(router.is_rule_active("Office hours", day));
+        assert!(router.is_rule_active("Removed rule", night));
+
+        // An invalid schedule is reported with the rule's other problems
+        config.routing_rules[0].schedule = Some(ScheduleConfig {
+            hours: vec!["9-18".to_string()],
+            ..Default::default()
+        });
+        let error = ChannelRouter::new(&config).err().unwrap().to_string();
+        assert!(error.contains("Invalid schedule in rule #1 'Office hours'"));
+        assert!(error.contains("Invalid time range '9-18'"));
+    }
+
+    #[test]
+    fn test_time_conditions() {
+        use chrono::TimeZone;
+
+        let rule = |name: &str, conditions: RuleMatch, channel: &str| RoutingRule {
+            name: name.to_string(),
+            match_conditions: RuleMatch {
+                hook_types: vec!["PermissionRequest".to_string()],
+                timezone: Some("Asia/Shanghai".to_string()),
+                ..conditions
+            },
+            channels: vec![channel.to_string()],
+            ..Default::default()
+        };
+        let mut config = create_test_config();
+        config.routing_rules = vec![
+            rule(
+                "Working hours",
+                RuleMatch {
+                    weekdays: vec!["weekdays".to_string()],
+                    time_window: vec!["09:00-18:00".to_string()],
+                    ..Default::default()
+                },
+                "dingtalk",
+            ),
+            RoutingRule {
+                default: true,
+                ..rule("After hours", RuleMatch::default(), "wechat")
+            },
+        ];
+        let router = ChannelRouter::new(&config).unwrap();
+        let input = HookInput::permission_request("s".to_string(), None, None, None, None);
+        let channels = |now| router.route_at(&input, &config, now).unwrap().channels;
+
+        // Wednesday 10:00 and 20:00, Saturday 10:00 in Shanghai
+        let wednesday_morning = Utc.with_ymd_and_hms(2026, 1, 7, 2, 0, 0).unwrap();
+        let wednesday_evening = Utc.with_ymd_and_hms(2026, 1, 7, 12, 0, 0).unwrap();
+        let saturday_morning = Utc.with_ymd_and_hms(2026, 1, 10, 2, 0, 0).unwrap();
+        assert_eq!(channels(wednesday_morning), vec!["dingtalk"]);
+        assert_eq!(channels(wednesday_evening), vec!["wechat"]);
+        assert_eq!(channels(saturday_morning), vec!["wechat"]);
+
+        let decision = router.route_at(&input, &config, wednesday_evening).unwrap();
+        assert_eq!(
+            decision.evaluations[0].outcome,
+            RuleOutcome::NotMatched {
+                reason:
+                    "2026-01-07T12:00:00Z is outside the rule's time window (weekdays 09:00-18:00 Asia/Shanghai)"
+                        .to_string()
+            }
+        );
+
+        config.routing_rules[0].match_conditions.time_window = vec!["9am-6pm".to_string()];
+        let error = ChannelRouter::new(&config).err().unwrap().to_string();
+        assert_eq!(
+            error,
+            "Routing error: Invalid time condition in rule #1 'Working hours': \
+             Invalid time range '9am-6pm', expected HH:MM-HH:MM"
+        );
+    }
+
+    #[test]
+    fn test_rule_fallbacks() {
+        let mut config = create_test_config();
+        config.routing_rules = vec![RoutingRule {
+            name: "Team chat".to_string(),
+            channels: vec!["wechat".to_string()],
+            fallback: vec!["dingtalk".to_string()],
+            ..Default::default()
+        }];
+
+        let router = ChannelRouter::new(&config).unwrap();
+        let input = HookInput::stop("test".to_string(), None, None);
+
+        let decision = router.route(&input, &config).unwrap();
+        assert_eq!(decision.rules, vec!["Team chat"]);
+        assert_eq!(decision.channels, vec!["wechat"]);
+        assert_eq!(decision.fallbacks["wechat"], vec!["dingtalk"]);
+    }
+
+    #[test]
+    fn test_rule_priority_stop_and_exclusion() {
+        let rule = |name: &str, hook_types: &[&str], channels: &[&str]| RoutingRule {
+            name: name.to_string(),
+            match_conditions: RuleMatch {
+                hook_types: hook_types.iter().map(|h| h.to_string()).collect(),
+                ..Default::default()
+            },
+            channels: channels.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+
+        let mut config = create_test_config();
+        config.routing_rules = vec![
+            RoutingRule {
+                default: true,
+                ..rule("Everything", &[], &["system", "dingtalk"])
+            },
+            rule("Stops", &["Stop"], &["wechat"]),
+            RoutingRule {
+                priority: 10,
+                stop: true,
+                ..rule("Tools", &["PreToolUse"], &["system"])
+            },
+            rule("Tools to chat", &["PreToolUse"], &["dingtalk"]),
+            RoutingRule {
+                exclude_channels: vec!["dingtalk".to_string()],
+                ..rule("Quiet notifications", &["Notification"], &[])
+            },
+            rule("Muted subagents", &["SubagentStop"], &[]),
+        ];
+        let router = ChannelRouter::new(&config).unwrap();
+        let route = |input: &HookInput| {
+            let decision = router.route(input, &config).unwrap();
+            (decision.rules, decision.channels)
+        };
+
+        // The higher-priority rule stops evaluation before "Tools to chat"
+        let tool_use = HookInput::pre_tool_use("s".to_string(), None, "Bash".to_string(), None);
+        assert_eq!(
+            route(&tool_use),
+            (vec!["Tools".to_string()], vec!["system".to_string()])
+        );
+
+        // The default rule only applies when nothing else matched
+        let stop = HookInput::stop("s".to_string(), None, None);
+        assert_eq!(
+            route(&stop),
+            (vec!["Stops".to_string()], vec!["wechat".to_string()])
+        );
+        let session_end = HookInput::session_end("s".to_string(), None, "other".to_string());
+        assert_eq!(route(&session_end).1, vec!["system", "dingtalk"]);
+
+        // An exclusion-only rule subtracts from the default rule's channels
+        let notification =
+            HookInput::notification("s".to_string(), None, "Waiting".to_string(), None);
+        assert_eq!(
+            route(&notification),
+            (
+                vec!["Quiet notifications".to_string(), "Everything".to_string()],
+                vec!["system".to_string()]
+            )
+        );
+
+        // A matching rule without channels or exclusions silences the input
+        let subagent = HookInput::subagent_stop("s".to_string(), None, None, None);
+        let decision = router.route(&subagent, &config).unwrap();
+        assert_eq!(decision.rules, vec!["Muted subagents"]);
+        assert!(decision.channels.is_empty());
+        assert!(!decision.used_defaults);
+    }
+
+    #[test]
+    fn test_rule_evaluations() {
+        let mut config = create_test_config();
+        let rule = |name: &str, conditions: RuleMatch| RoutingRule {
+            name: name.to_string(),
+            match_conditions: conditions,
+            channels: vec!["dingtalk".to_string()],
+            ..Default::default()
+        };
+        config.routing_rules = vec![
+            rule(
+                "Questions",
+                RuleMatch {
+                    message_pattern: Some("\\?$".to_string()),
+                    ..Default::default()
+                },
+            ),
+            rule(
+                "Tools",
+                RuleMatch {
+                    tool_pattern: Some("Bash".to_string()),
+                    ..Default::default()
+                },
+            ),
+            RoutingRule {
+                enabled: false,
+                ..rule("Off", RuleMatch::default())
+            },
+            RoutingRule {
+                stop: true,
+                ..rule("Notifications", RuleMatch::default())
+            },
+            rule("Never reached", RuleMatch::default()),
+            RoutingRule {
+                default: true,
+                ..rule("Fallback", RuleMatch::default())
+            },
+        ];
+        let router = ChannelRouter::new(&config).unwrap();
+
+        let input = HookInput::notification("s".to_string(), None, "Done".to_string(), None);
+        let decision = router.route(&input, &config).unwrap();
+        let outcomes: Vec<_> = decision
+            .evaluations
+            .iter()
+            .map(|evaluation| (evaluation.rule.as_str(), evaluation.outcome.clone()))
+            .collect();
+
+        let not_matched = |reason: &str| RuleOutcome::NotMatched {
+            reason: reason.to_string(),
+        };
+        let skipped = |reason: &str| RuleOutcome::Skipped {
+            reason: reason.to_string(),
+        };
+        assert_eq!(
+            outcomes,
+            vec![
+                (
+                    "Questions",
+                    not_matched("message \"Done\" does not match message_pattern /\\?$/")
+                ),
+                (
+                    "Tools",
+                    not_matched("tool_pattern only matches PreToolUse hooks")
+                ),
+                ("Off", RuleOutcome::Disabled),
+                ("Notifications", RuleOutcome::Matched { active: true }),
+                (
+                    "Never reached",
+                    skipped("rule 'Notifications' stopped evaluation")
+                ),
+                ("Fallback", skipped("a more specific rule matched")),
+            ]
+        );
+        assert!(!decision.used_defaults);
+
+        config.routing_rules.truncate(2);
+        let router = ChannelRouter::new(&config).unwrap();
+        assert!(router.route(&input, &config).unwrap().used_defaults);
+    }
+
+    #[test]
+    fn test_rule_expression() {
+        let mut config = create_test_config();
+        config.routing_rules = vec![RoutingRule {
+            name: "Dangerous commands".to_string(),
+            match_conditions: RuleMatch {
+                expression: Some(
+                    r#"hook == "PreToolUse" and tool_input.command =~ "rm -rf" or message =~ "(?i)urgent""#
+                        .to_string(),
+                ),
+                ..Default::default()
+            },
+            channels: vec!["dingtalk".to_string()],
+            ..Default::default()
+        }];
+        let router = ChannelRouter::new(&config).unwrap();
+
+        let tool_use = |command: &str| {
+            serde_json::from_value::<HookInput>(serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "test",
+                "tool_name": "Bash",
+                "tool_input": {"command": command}
+            }))
+            .unwrap()
+        };
+        let channels = |input: &HookInput| router.match_channels(input, &config).unwrap();
+
+        assert_eq!(channels(&tool_use("rm -rf build")), vec!["dingtalk"]);
+        assert_eq!(channels(&tool_use("cargo build")), vec!["system"]);
+
+        let urgent = HookInput::notification(
+            "test".to_string(),
+            None,
+            "URGENT: approve".to_string(),
+            None,
+        );
+        assert_eq!(channels(&urgent), vec!["dingtalk"]);
+    }
+
+    #[test]
+    fn test_invalid_rule_expression() {
+        let mut config = create_test_config();
+        config.routing_rules = vec![RoutingRule {
+            name: "Broken".to_string(),
+            match_conditions: RuleMatch {
+                expression: Some("hook = 'Stop'".to_string()),
+                ..Default::default()
+            },
+            channels: vec!["system".to_string()],
+            ..Default::default()
+        }];
+
+        let error = ChannelRouter::new(&config).err().unwrap().to_string();
+        assert_eq!(
+            error,
+            "Routing error: Invalid expression in rule #1 'Broken': Unexpected '=', use '==' to compare at column 6"
+        );
+    }
+
+    #[test]
+    fn test_tool_input_rules() {
+        let rule = |name: &str, conditions: Vec<ToolInputMatch>| RoutingRule {
+            name: name.to_string(),
+            match_conditions: RuleMatch {
+                tool_input: conditions,
+                ..Default::default()
+            },
+            channels: vec!["dingtalk".to_string()],
+            ..Default::default()
+        };
+        let condition = |path: &str, regex: Option<&str>, glob: Option<&str>| ToolInputMatch {
+            path: path.to_string(),
+            regex: regex.map(str::to_string),
+            glob: glob.map(str::to_string),
+        };
+
+        let mut config = create_test_config();
+        config.routing_rules = vec![
+            rule(
+                "Publishing",
+                vec![condition("$.command", Some("git push|npm publish"), None)],
+            ),
+            rule(
+                "Production files",
+                vec![condition("$..file_path", None, Some("**/prod/**"))],
+            ),
+            rule(
+                "Home directory",
+                vec![condition("$.command", Some("^~/"), None)],
+            ),
+            rule(
+                "Directory removal",
+                vec![condition("$.command", Some("^rm -r \\S+/$"), None)],
+            ),
+        ];
+        let router = ChannelRouter::new(&config).unwrap();
+
+        let tool_use = |tool: &str, tool_input: Value| {
+            let input = serde_json::from_value::<HookInput>(serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "test",
+                "tool_name": tool,
+                "tool_input": tool_input
+            }))
+            .unwrap();
+            router.route(&input, &config).unwrap().rules
+        };
+
+        assert_eq!(
+            tool_use(
+                "Bash",
+                serde_json::json!({"command": "git push origin main"})
+            ),
+            vec!["Publishing"]
+        );
+        assert!(tool_use("Bash", serde_json::json!({"command": "git status"})).is_empty());
+        assert_eq!(
+            tool_use(
+                "MultiEdit",
+                serde_json::json!({"edits": [{"file_path": "/srv/prod/app.env"}]})
+            ),
+            vec!["Production files"]
+        );
+        assert!(tool_use("Edit", serde_json::json!({"file_path": "/srv/dev/app.env"})).is_empty());
+        assert!(tool_use("Bash", Value::Null).is_empty());
+
+        // Regexes see the value as is: no `~` expansion, trailing `/` kept
+        assert_eq!(
+            tool_use("Bash", serde_json::json!({"command": "~/bin/deploy"})),
+            vec!["Home directory"]
+        );
+        assert_eq!(
+            tool_use("Bash", serde_json::json!({"command": "rm -r build/"})),
+            vec!["Directory removal"]
+        );
+        assert!(tool_use("Bash", serde_json::json!({"command": "rm -r build"})).is_empty());
+
+        config.routing_rules = vec![rule(
+            "Ambiguous",
+            vec![condition("$.command", Some("rm"), Some("rm *"))],
+        )];
+        let error = ChannelRouter::new(&config).err().unwrap().to_string();
+        assert!(error.contains("Invalid tool_input in rule #1 'Ambiguous'"));
+    }
+
+    #[test]
+    fn test_status_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let transcript = |name: &str, tool: &str| {
+            let path = dir.path().join(format!("{}.jsonl", name));
+            let line = serde_json::json!({
+                "type": "assistant",
+                "timestamp": "2026-01-01T00:00:00Z",
+                "message": {"content": [{"type": "tool_use", "name": tool, "input": {}}]}
+            });
+            std::fs::write(&path, line.to_string()).unwrap();
+            path.display().to_string()
+        };
+
+        let mut config = create_test_config();
+        config.routing_rules = vec![RoutingRule {
+            name: "Needs attention".to_string(),
+            match_conditions: RuleMatch {
+                status: vec!["Question".to_string(), "PlanReady".to_string()],
+                ..Default::default()
+            },
+            channels: vec!["dingtalk".to_string()],
+            ..Default::default()
+        }];
+        let router = ChannelRouter::new(&config).unwrap();
+        let channels = |input: &HookInput| router.match_channels(input, &config).unwrap();
+
+        let stop = |path: String| HookInput::stop("test".to_string(), Some(path), None);
+        assert_eq!(
+            channels(&stop(transcript("question", "AskUserQuestion"))),
+            vec!["dingtalk"]
+        );
+        assert_eq!(
+            channels(&stop(transcript("plan", "ExitPlanMode"))),
+            vec!["dingtalk"]
+        );
+
+        // Routing analyzes the transcript once; clones reuse the status
+        let path = transcript("reused", "AskUserQuestion");
+        let input = stop(path.clone());
+        assert_eq!(channels(&input), vec!["dingtalk"]);
+        std::fs::remove_file(&path).unwrap();
+        let input = input.clone();
+        assert_eq!(input.transcript_status(), Some(Status::Question));
+        assert_eq!(channels(&input), vec!["dingtalk"]);
+        assert_eq!(Severity::analyze(&input), Severity::Warning);
+        assert_eq!(
+            channels(&HookInput::stop("test".to_string(), None, None)),
+            vec!["system"]
+        );
+
+        // Other hooks have no status, even with a transcript
+        let notification = HookInput::notification(
+            "test".to_string(),
+            Some(transcript("notified", "AskUserQuestion")),
+            "Waiting".to_string(),
+            None,
+        );
+        assert_eq!(channels(&notification), vec!["system"]);
+
+        config.routing_rules[0].match_conditions.status = vec!["Questions".to_string()];
+        let error = ChannelRouter::new(&config).err().unwrap().to_string();
+        assert_eq!(
+            error,
+            "Routing error: Invalid status in rule #1 'Needs attention': unknown status 'Questions'"
+        );
+    }
+
+    #[test]
+    fn test_severity_rules() {
+        let rule = |name: &str, conditions: RuleMatch, channels: &[&str]| RoutingRule {
+            name: name.to_string(),
+            match_conditions: conditions,
+            channels: channels.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+
+        let mut config = create_test_config();
+        config.routing_rules = vec![
+            RoutingRule {
+                severity: Some(Severity::Critical),
+                ..rule(
+                    "Production pushes",
+                    RuleMatch {
+                        tool_pattern: Some("^Bash$".to_string()),
+                        ..Default::default()
+                    },
+                    &[],
+                )
+            },
+            rule(
+                "Page on warnings",
+                RuleMatch {
+                    min_severity: Some(Severity::Warning),
+                    ..Default::default()
+                },
+                &["dingtalk"],
+            ),
+        ];
+        let router = ChannelRouter::new(&config).unwrap();
+        let route = |input: &HookInput| router.route(input, &config).unwrap();
+
+        // Permission requests default to warning
+        let permission = HookInput::permission_request("test".to_string(), None, None, None, None);
+        let decision = route(&permission);
+        assert_eq!(decision.severity, Severity::Warning);
+        assert_eq!(decision.channels, vec!["dingtalk"]);
+
+        // Notifications default to notice, below the rule's minimum
+        let notification =
+            HookInput::notification("test".to_string(), None, "Waiting".to_string(), None);
+        let decision = route(&notification);
+        assert_eq!(decision.severity, Severity::Notice);
+        assert_eq!(decision.channels, vec!["system"]);
+        assert_eq!(
+            decision.evaluations[1].outcome,
+            RuleOutcome::NotMatched {
+                reason: "severity notice is below min_severity warning".to_string()
+            }
+        );
+
+        // An earlier rule raises the severity for the rules after it
+        let bash = HookInput::pre_tool_use("test".to_string(), None, "Bash".to_string(), None);
+        let decision = route(&bash);
+        assert_eq!(decision.severity, Severity::Critical);
+        assert_eq!(decision.channels, vec!["dingtalk"]);
+        let read = HookInput::pre_tool_use("test".to_string(), None, "Read".to_string(), None);
+        assert_eq!(route(&read).severity, Severity::Info);
+
+        // A severity given with the input replaces the default
+        let given = HookInput {
+            severity: Some(Severity::Critical),
+            ..notification
+        };
+        assert_eq!(route(&given).channels, vec!["dingtalk"]);
+    }
+
+    #[test]
+    fn test_invalid_rules_reported_together() {
+        let rule = |name: &str, conditions: RuleMatch| RoutingRule {
+            name: name.to_string(),
+            match_conditions: conditions,
+            channels: vec!["system".to_string()],
+            enabled: false,
+            ..Default::default()
+        };
+        let mut config = create_test_config();
+        config.routing_rules = vec![
+            rule(
+                "Errors",
+                RuleMatch {
+                    message_pattern: Some("(error".to_string()),
+                    tool_pattern: Some("Bash".to_string()),
+                    ..Default::default()
+                },
+            ),
+            rule("Fine", RuleMatch::default()),
+            rule(
+                "Tools",
+                RuleMatch {
+                    tool_pattern: Some("Bash|[".to_string()),
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        let error = ChannelRouter::new(&config).err().unwrap().to_string();
+        assert_eq!(
+            error,
+            "Routing error: Invalid message_pattern in rule #1 'Errors': unclosed group at column 1\n\
+             Invalid tool_pattern in rule #3 'Tools': unclosed character class at column 6"
+        );
+
+        // Delivery leaves the broken rules out and routes with the rest
+        config.routing_rules[1].enabled = true;
+        config.routing_rules[1].channels = vec!["dingtalk".to_string()];
+        let router = ChannelRouter::lenient(&config);
+        let stop = HookInput::stop("test".to_string(), None, None);
+        let decision = router.route(&stop, &config).unwrap();
+        assert_eq!(decision.rules, vec!["Fine"]);
+        assert_eq!(decision.channels, vec!["dingtalk"]);
+    }
+
+    #[test]
+    fn test_session_and_environment_rules() {
+        let rule = |name: &str, conditions: RuleMatch| RoutingRule {
+            name: name.to_string(),
+            match_conditions: conditions,
+            channels: vec!["dingtalk".to_string()],
+            ..Default::default()
+        };
+        let mut config = create_test_config();
+        config.routing_rules = vec![
+            rule(
+                "Bypassing",
+                RuleMatch {
+                    permission_modes: vec!["bypassPermissions".to_string()],
+                    session_pattern: Some("^ci-".to_string()),
+                    ..Default::default()
+                },
+            ),
+            rule(
+                "This machine in CI",
+                RuleMatch {
+                    hostname: Some(hostname().to_string()),
+                    env: HashMap::from([
+                        ("CCN_ROUTER_TEST_CI".to_string(), Some("true".to_string())),
+                        ("CCN_ROUTER_TEST_UNSET".to_string(), None),
+                    ]),
+                    ..Default::default()
+                },
+            ),
+        ];
+        let router = ChannelRouter::new(&config).unwrap();
+        let rules = |session_id: &str, mode: Option<&str>| {
+            let mut input = HookInput::stop(session_id.to_string(), None, None);
+            input.common.permission_mode = mode.map(str::to_string);
+            router.route(&input, &config).unwrap().rules
+        };
+
+        assert_eq!(rules("ci-42", Some("bypassPermissions")), vec!["Bypassing"]);
+        assert!(rules("ci-42", Some("default")).is_empty());
+        assert!(rules("laptop-1", Some("bypassPermissions")).is_empty());
+        assert!(rules("ci-42", None).is_empty());
+
+        std::env::set_var("CCN_ROUTER_TEST_CI", "true");
+        assert_eq!(rules("laptop-1", None), vec!["This machine in CI"]);
+        std::env::set_var("CCN_ROUTER_TEST_CI", "false");
+        assert!(rules("laptop-1", None).is_empty());
+        std::env::remove_var("CCN_ROUTER_TEST_CI");
+        assert!(rules("laptop-1", None).is_empty());
+
+        // A hook forwarded from another process routes by its environment
+        let forwarded = |env: &[(&str, &str)]| {
+            let env = env
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            let input = HookInput::stop("laptop-1".to_string(), None, None).with_env(env);
+            router.route(&input, &config).unwrap().rules
+        };
+        assert_eq!(
+            forwarded(&[("CCN_ROUTER_TEST_CI", "true")]),
+            vec!["This machine in CI"]
+        );
+        std::env::set_var("CCN_ROUTER_TEST_CI", "true");
+        assert!(forwarded(&[]).is_empty());
+        assert!(forwarded(&[
+            ("CCN_ROUTER_TEST_CI", "true"),
+            ("CCN_ROUTER_TEST_UNSET", "1")
+        ])
+        .is_empty());
+        assert_eq!(
+            ChannelRouter::captured_env(&config),
+            HashMap::from([("CCN_ROUTER_TEST_CI".to_string(), "true".to_string())])
+        );
+        std::env::remove_var("CCN_ROUTER_TEST_CI");
+    }
+
+    #[test]
+    fn test_cwd_and_project_rules() {
+        let work = tempfile::tempdir().unwrap();
+        let repo = work.path().join("client-a").join("api");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+
+        let rule = |name: &str, conditions: RuleMatch, channel: &str| RoutingRule {
+            name: name.to_string(),
+            match_conditions: conditions,
+            channels: vec![channel.to_string()],
+            ..Default::default()
+        };
+        let mut config = create_test_config();
+        config.routing_rules = vec![
+            rule(
+                "Client A",
+                RuleMatch {
+                    cwd_pattern: Some(format!("{}/client-a/**", work.path().display())),
+                    project: Some("{api,web}".to_string()),
+                    ..Default::default()
+                },
+                "dingtalk",
+            ),
+            rule(
+                "Scratch",
+                RuleMatch {
+                    project: Some("re:^scratch-".to_string()),
+                    ..Default::default()
+                },
+                "custom",
+            ),
+        ];
+        let router = ChannelRouter::new(&config).unwrap();
+
+        let in_dir = |cwd: Option<&std::path::Path>| {
+            let mut input = HookInput::stop("test".to_string(), None, None);
+            input.common.cwd = cwd.map(|cwd| cwd.display().to_string());
+            router.match_channels(&input, &config).unwrap()
+        };
+
+        assert_eq!(in_dir(Some(&repo.join("src"))), vec!["dingtalk"]);
+        assert_eq!(in_dir(Some(&work.path().join("scratch-1"))), vec!["custom"]);
+        assert_eq!(in_dir(Some(work.path())), vec!["system"]);
+        assert_eq!(in_dir(None), vec!["system"]);
+
+        config.routing_rules[0].match_conditions.cwd_pattern = Some("/work/{a".to_string());
+        let error = ChannelRouter::new(&config).err().unwrap().to_string();
+        assert!(error.contains("Invalid cwd_pattern in rule #1 'Client A'"));
+    }
+
     #[test]
     fn test_disabled_rule() {
         let mut config = create_test_config();
@@ -301,9 +1767,10 @@ mod tests {
             },
             channels: vec!["custom".to_string()],
             enabled: false,
+            ..Default::default()
         }];
 
-        let router = ChannelRouter::new(&config);
+        let router = ChannelRouter::new(&config).unwrap();
 
         let stop_input = HookInput::stop("test".to_string(), None, Some("Test stop".to_string()));
 
@@ -0,0 +1,193 @@
+//! Active-hours schedules (quiet hours / do-not-disturb)
+//!
+//! A schedule lists the days and time ranges when a channel or routing rule is
+//! active, evaluated in a configurable timezone. Outside those hours the
+//! notification is skipped or deferred to the outbox, depending on the
+//! schedule's `outside_hours` policy.
+
+use chrono::{DateTime, Datelike, Local, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::config::ScheduleConfig;
+use crate::error::{NotificationError, Result};
+
+/// A parsed [`ScheduleConfig`]
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    days: Vec<Weekday>,
+    ranges: Vec<(NaiveTime, NaiveTime)>,
+    timezone: Option<Tz>,
+}
+
+impl Schedule {
+    /// Parse a schedule configuration
+    pub fn parse(config: &ScheduleConfig) -> Result<Self> {
+        Self::from_parts(&config.days, &config.hours, config.timezone.as_deref())
+    }
+
+    /// Parse days, time ranges and a timezone given separately
+    pub fn from_parts(days: &[String], hours: &[String], timezone: Option<&str>) -> Result<Self> {
+        let days = days
+            .iter()
+            .map(|day| parse_days(day))
+            .collect::<Result<Vec<_>>>()?
+            .concat();
+
+        let ranges = hours
+            .iter()
+            .map(|range| parse_range(range))
+            .collect::<Result<Vec<_>>>()?;
+
+        let timezone = timezone
+            .map(|name| {
+                name.parse::<Tz>().map_err(|_| {
+                    NotificationError::ConfigError(format!("Unknown timezone: {}", name))
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            days,
+            ranges,
+            timezone,
+        })
+    }
+
+    /// Check whether the schedule is active at the given instant
+    ///
+    /// The day of week is taken from the local date, so a range that wraps
+    /// past midnight (e.g. "22:00-02:00") uses the current day for both parts.
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        let (weekday, time) = match &self.timezone {
+            Some(tz) => {
+                let local = now.with_timezone(tz);
+                (local.weekday(), local.time())
+            }
+            None => {
+                let local = now.with_timezone(&Local);
+                (local.weekday(), local.time())
+            }
+        };
+
+        if !self.days.is_empty() && !self.days.contains(&weekday) {
+            return false;
+        }
+
+        self.ranges.is_empty()
+            || self.ranges.iter().any(|(start, end)| {
+                if start < end {
+                    time >= *start && time < *end
+                } else {
+                    // Wraps past midnight ("00:00" as end means end of day)
+                    time >= *start || time < *end
+                }
+            })
+    }
+}
+
+/// Check whether a schedule is active now
+///
+/// Loaded configurations have their channel schedules validated; an invalid
+/// one that gets here anyway is reported and treated as always active, so a
+/// typo never silences a channel.
+pub fn is_active(config: &ScheduleConfig, now: DateTime<Utc>) -> bool {
+    match Schedule::parse(config) {
+        Ok(schedule) => schedule.is_active_at(now),
+        Err(e) => {
+            eprintln!("Warning: Ignoring invalid schedule: {}", e);
+            true
+        }
+    }
+}
+
+/// Parse a day name or a "weekdays"/"weekends" shorthand
+fn parse_days(day: &str) -> Result<Vec<Weekday>> {
+    match day.trim().to_lowercase().as_str() {
+        "weekdays" => Ok(vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ]),
+        "weekends" => Ok(vec![Weekday::Sat, Weekday::Sun]),
+        name => name
+            .parse::<Weekday>()
+            .map(|weekday| vec![weekday])
+            .map_err(|_| NotificationError::ConfigError(format!("Invalid day: {}", day))),
+    }
+}
+
+/// Parse an "HH:MM-HH:MM" time range
+fn parse_range(range: &str) -> Result<(NaiveTime, NaiveTime)> {
+    let invalid = || {
+        NotificationError::ConfigError(format!(
+            "Invalid time range '{}', expected HH:MM-HH:MM",
+            range
+        ))
+    };
+
+    let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+    let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| invalid())?;
+    let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").map_err(|_| invalid())?;
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn schedule(days: &[&str], hours: &[&str], timezone: &str) -> Schedule {
+        Schedule::parse(&ScheduleConfig {
+            days: days.iter().map(|d| d.to_string()).collect(),
+            hours: hours.iter().map(|h| h.to_string()).collect(),
+            timezone: Some(timezone.to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_working_hours() {
+        let schedule = schedule(&["weekdays"], &["09:00-18:00"], "Asia/Shanghai");
+
+        // Wednesday 2024-01-03 10:00 in Shanghai (02:00 UTC)
+        let active = Utc.with_ymd_and_hms(2024, 1, 3, 2, 0, 0).unwrap();
+        assert!(schedule.is_active_at(active));
+
+        // Wednesday 03:00 in Shanghai
+        let night = Utc.with_ymd_and_hms(2024, 1, 2, 19, 0, 0).unwrap();
+        assert!(!schedule.is_active_at(night));
+
+        // Saturday 2024-01-06 10:00 in Shanghai
+        let weekend = Utc.with_ymd_and_hms(2024, 1, 6, 2, 0, 0).unwrap();
+        assert!(!schedule.is_active_at(weekend));
+    }
+
+    #[test]
+    fn test_range_wrapping_midnight() {
+        let schedule = schedule(&[], &["22:00-02:00"], "UTC");
+
+        assert!(schedule.is_active_at(Utc.with_ymd_and_hms(2024, 1, 3, 23, 0, 0).unwrap()));
+        assert!(schedule.is_active_at(Utc.with_ymd_and_hms(2024, 1, 3, 1, 59, 0).unwrap()));
+        assert!(!schedule.is_active_at(Utc.with_ymd_and_hms(2024, 1, 3, 2, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_invalid_schedule() {
+        let config = ScheduleConfig {
+            hours: vec!["9am-5pm".to_string()],
+            ..Default::default()
+        };
+        assert!(Schedule::parse(&config).is_err());
+        // Invalid schedules never silence a channel
+        assert!(is_active(&config, Utc::now()));
+
+        let config = ScheduleConfig {
+            timezone: Some("Mars/Olympus".to_string()),
+            ..Default::default()
+        };
+        assert!(Schedule::parse(&config).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Notification severity levels
+//!
+//! Every notification carries a severity, from `info` to `critical`. It is
+//! derived from the hook type and, for Stop and SubagentStop hooks, the
+//! transcript status; routing rules can raise or lower it and match on it,
+//! and channels use it to decide how loudly to deliver.
+
+use crate::analyzer::Status;
+use crate::hooks::{HookData, HookInput, HookType};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How urgently a notification needs the user's attention
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Progress the user may want to know about
+    #[default]
+    Info,
+    /// Claude finished or is waiting, nothing is blocked
+    Notice,
+    /// Claude is blocked until the user answers or approves
+    Warning,
+    /// The session cannot continue without the user fixing something
+    Critical,
+}
+
+impl Severity {
+    /// Every severity, from least to most severe
+    pub const ALL: &'static [Severity] = &[
+        Severity::Info,
+        Severity::Notice,
+        Severity::Warning,
+        Severity::Critical,
+    ];
+
+    /// Lowercase name, as used in configuration and templates
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Notice => "notice",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// Default severity of a hook input
+    ///
+    /// `status` is only called for Stop and SubagentStop hooks, whose
+    /// severity depends on what the transcript shows. The hook type comes
+    /// from `hook_event_name`: `data` is parsed by shape, so a SubagentStop
+    /// input parses as Stop data and a PermissionRequest with a `tool_name`
+    /// as PreToolUse data.
+    pub fn of(input: &HookInput, status: impl FnOnce() -> Option<Status>) -> Self {
+        match input.hook_event_name {
+            HookType::PermissionRequest => Severity::Warning,
+            HookType::Notification => match &input.data {
+                HookData::Notification(data) => match data.notification_type.as_deref() {
+                    Some("permission_prompt") | Some("elicitation_dialog") => Severity::Warning,
+                    Some("auth_success") => Severity::Info,
+                    _ => Severity::Notice,
+                },
+                _ => Severity::Notice,
+            },
+            HookType::Stop => match status() {
+                Some(Status::SessionLimitReached) | Some(Status::APIError) => Severity::Critical,
+                Some(Status::Question) | Some(Status::PlanReady) => Severity::Warning,
+                _ => Severity::Notice,
+            },
+            HookType::SubagentStop => match status() {
+                Some(Status::SessionLimitReached) | Some(Status::APIError) => Severity::Critical,
+                _ => Severity::Info,
+            },
+            HookType::PreToolUse | HookType::SessionEnd => Severity::Info,
+        }
+    }
+
+    /// Severity a hook input carries, or its default from the transcript
+    pub fn analyze(input: &HookInput) -> Self {
+        input
+            .severity
+            .unwrap_or_else(|| Self::of(input, || input.transcript_status()))
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Severity::ALL
+            .iter()
+            .copied()
+            .find(|severity| severity.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "unknown severity '{}' (expected info, notice, warning or critical)",
+                    s
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ordering_and_names() {
+        assert!(Severity::Info < Severity::Notice);
+        assert!(Severity::Warning < Severity::Critical);
+        assert_eq!("Critical".parse::<Severity>(), Ok(Severity::Critical));
+        assert!("loud".parse::<Severity>().is_err());
+        assert_eq!(
+            serde_json::to_string(&Severity::Warning).unwrap(),
+            "\"warning\""
+        );
+    }
+
+    #[test]
+    fn test_default_severity() {
+        let no_status = || panic!("status is only needed for Stop hooks");
+
+        let permission = HookInput::permission_request(
+            "s".to_string(),
+            None,
+            Some("Bash".to_string()),
+            None,
+            None,
+        );
+        assert_eq!(Severity::of(&permission, no_status), Severity::Warning);
+
+        let notification =
+            HookInput::notification("s".to_string(), None, "Waiting".to_string(), None);
+        assert_eq!(Severity::of(&notification, no_status), Severity::Notice);
+
+        let tool = HookInput::pre_tool_use("s".to_string(), None, "Bash".to_string(), None);
+        assert_eq!(Severity::of(&tool, no_status), Severity::Info);
+
+        let stop = HookInput::stop("s".to_string(), None, None);
+        assert_eq!(
+            Severity::of(&stop, || Some(Status::APIError)),
+            Severity::Critical
+        );
+        assert_eq!(
+            Severity::of(&stop, || Some(Status::Question)),
+            Severity::Warning
+        );
+        assert_eq!(
+            Severity::of(&stop, || Some(Status::TaskComplete)),
+            Severity::Notice
+        );
+        assert_eq!(Severity::of(&stop, || None), Severity::Notice);
+
+        let subagent = HookInput::subagent_stop("s".to_string(), None, None, None);
+        assert_eq!(
+            Severity::of(&subagent, || Some(Status::TaskComplete)),
+            Severity::Info
+        );
+    }
+
+    #[test]
+    fn test_default_severity_of_parsed_input() {
+        let parse = |json: serde_json::Value| serde_json::from_value::<HookInput>(json).unwrap();
+
+        let subagent = parse(serde_json::json!({
+            "hook_event_name": "SubagentStop",
+            "session_id": "s",
+            "stop_hook_active": false
+        }));
+        assert!(matches!(subagent.data, HookData::Stop(_)));
+        assert_eq!(
+            Severity::of(&subagent, || Some(Status::TaskComplete)),
+            Severity::Info
+        );
+
+        let permission = parse(serde_json::json!({
+            "hook_event_name": "PermissionRequest",
+            "session_id": "s",
+            "tool_name": "Bash",
+            "tool_input": {"command": "rm -rf build"}
+        }));
+        assert!(matches!(permission.data, HookData::PreToolUse(_)));
+        assert_eq!(
+            Severity::of(&permission, || panic!("no transcript status")),
+            Severity::Warning
+        );
+
+        let notification = parse(serde_json::json!({
+            "hook_event_name": "Notification",
+            "session_id": "s",
+            "message": "Claude needs your permission to use Bash",
+            "notification_type": "permission_prompt"
+        }));
+        assert_eq!(Severity::analyze(&notification), Severity::Warning);
+    }
+}
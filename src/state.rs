@@ -0,0 +1,189 @@
+//! Persistent runtime state shared across hook processes
+//!
+//! Hook invocations are short-lived processes, so anything that must survive
+//! between notifications (rate limit buckets, for example) is kept in a small
+//! JSON state file under the state directory. Access is serialized with a
+//! lock file so concurrent hooks do not lose each other's updates.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::circuit::CircuitState;
+use crate::config::get_state_dir;
+use crate::error::{NotificationError, Result};
+use crate::escalation::PendingEscalation;
+use crate::outbox::OutboxEntry;
+use crate::ratelimit::TokenBucket;
+
+/// How long to wait for the state lock before giving up
+const LOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Lock files older than this are assumed to be left behind by a crashed process
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10);
+
+/// Runtime state persisted between hook invocations
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RuntimeState {
+    /// Token buckets keyed by channel id
+    pub rate_limits: HashMap<String, TokenBucket>,
+
+    /// First-seen times (ms since epoch) keyed by deduplication key
+    pub dedup: HashMap<String, i64>,
+
+    /// Notifications deferred by quiet hours or batched for digests
+    pub outbox: Vec<OutboxEntry>,
+
+    /// Escalation steps waiting for their delay to elapse
+    pub escalations: Vec<PendingEscalation>,
+
+    /// Failure tracking keyed by channel id
+    pub circuits: HashMap<String, CircuitState>,
+}
+
+/// File-backed store for [`RuntimeState`]
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    path: PathBuf,
+}
+
+impl StateStore {
+    /// Create a store backed by a specific file
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Get the default state file path
+    /// Returns ~/.claude-code-notifications/state.json
+    pub fn default_path() -> PathBuf {
+        get_state_dir().join("state.json")
+    }
+
+    /// Read a snapshot of the current state
+    pub fn load(&self) -> Result<RuntimeState> {
+        read_state(&self.path)
+    }
+
+    /// Apply an update to the state under an exclusive lock and persist it
+    pub fn update<T>(&self, f: impl FnOnce(&mut RuntimeState) -> T) -> Result<T> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let _lock = StateLock::acquire(&self.path.with_extension("lock"))?;
+        let mut state = read_state(&self.path)?;
+        let result = f(&mut state);
+
+        // Write to a temporary file and rename so readers never see a partial file
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&state)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        Ok(result)
+    }
+}
+
+impl Default for StateStore {
+    fn default() -> Self {
+        Self::new(Self::default_path())
+    }
+}
+
+/// Read state from disk, treating a missing or corrupt file as empty state
+fn read_state(path: &Path) -> Result<RuntimeState> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Warning: Ignoring corrupt state file {:?}: {}", path, e);
+            RuntimeState::default()
+        })),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(RuntimeState::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Exclusive lock held by creating a lock file; released on drop
+pub(crate) struct StateLock {
+    path: PathBuf,
+}
+
+impl StateLock {
+    /// Create the lock file, waiting while another process holds it
+    pub(crate) fn acquire(path: &Path) -> Result<Self> {
+        let started = SystemTime::now();
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(_) => {
+                    return Ok(Self {
+                        path: path.to_path_buf(),
+                    })
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if is_stale(path) {
+                        let _ = fs::remove_file(path);
+                        continue;
+                    }
+                    if started.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                        return Err(NotificationError::InvalidInput(format!(
+                            "Timed out waiting for lock {:?}",
+                            path
+                        )));
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Check whether a lock file was left behind by a crashed process
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > STALE_LOCK_AGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_state_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = StateStore::new(dir.path().join("state.json"));
+
+        let state = store.load().unwrap();
+        assert!(state.rate_limits.is_empty());
+    }
+
+    #[test]
+    fn test_update_persists_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = StateStore::new(dir.path().join("nested").join("state.json"));
+
+        store
+            .update(|state| {
+                state
+                    .rate_limits
+                    .insert("dingtalk".to_string(), TokenBucket::default());
+            })
+            .unwrap();
+
+        let state = store.load().unwrap();
+        assert!(state.rate_limits.contains_key("dingtalk"));
+        assert!(!dir.path().join("nested").join("state.lock").exists());
+    }
+}
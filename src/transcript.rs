@@ -262,6 +262,18 @@ pub fn get_last_assistant_timestamp(messages: &[Message]) -> String {
         .unwrap_or_default()
 }
 
+/// Time of the latest user entry
+///
+/// Tool results count too, since answering a permission prompt or a
+/// question is recorded as one.
+pub fn last_user_entry_at(messages: &[Message]) -> Option<DateTime<Utc>> {
+    messages
+        .iter()
+        .filter(|msg| msg.message_type == MessageType::User)
+        .filter_map(|msg| parse_timestamp(&msg.timestamp).ok())
+        .max()
+}
+
 /// Parse RFC3339 timestamp
 fn parse_timestamp(ts: &str) -> Result<DateTime<Utc>> {
     ts.parse::<DateTime<Utc>>().map_err(|e| {
@@ -291,6 +303,35 @@ mod tests {
         assert_eq!(filtered.len(), 1);
     }
 
+    #[test]
+    fn test_last_user_entry_at() {
+        let message = |content: ContentBlock| Message {
+            message_type: MessageType::User,
+            message: MessageContent {
+                content: vec![content],
+            },
+            timestamp: "2024-01-01T00:10:00Z".to_string(),
+            parent_uuid: None,
+        };
+        let text = vec![message(ContentBlock::Text {
+            text: "yes, go ahead".to_string(),
+        })];
+        assert_eq!(
+            last_user_entry_at(&text),
+            Some(parse_timestamp("2024-01-01T00:10:00Z").unwrap())
+        );
+
+        let tool_result = vec![message(ContentBlock::ToolResult {
+            tool_use_id: "t1".to_string(),
+            content: "ok".to_string(),
+            is_error: false,
+        })];
+        assert_eq!(
+            last_user_entry_at(&tool_result),
+            Some(parse_timestamp("2024-01-01T00:10:00Z").unwrap())
+        );
+    }
+
     #[test]
     fn test_extract_tools_from_empty_messages() {
         let tools = extract_tools(&[]);
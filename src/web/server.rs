@@ -9,7 +9,11 @@ use std::path::PathBuf;
 use std::sync::Mutex;
 
 use crate::channels::r#trait::NotificationChannel;
-use crate::config::AppConfig;
+use crate::circuit::circuit_status;
+use crate::config::{validate_config, AppConfig};
+use crate::hooks::HookInput;
+use crate::router::ChannelRouter;
+use crate::state::StateStore;
 
 /// Start the web server on the specified port
 pub async fn start_web_server(
@@ -40,6 +44,8 @@ pub async fn start_web_server(
             .service(api_save_config)
             .service(api_test_channel)
             .service(api_list_channels)
+            .service(api_channel_status)
+            .service(api_explain)
             .service(fs::Files::new("/static", "./static").show_files_listing())
     })
     .bind(("127.0.0.1", port))?
@@ -86,6 +92,14 @@ async fn api_save_config(
     config_path: web::Data<Mutex<PathBuf>>,
     new_config: web::Json<AppConfig>,
 ) -> impl Responder {
+    // Reject channel schedules and rule expressions that do not compile
+    let compiled = validate_config(&new_config).and_then(|()| ChannelRouter::new(&new_config));
+    if let Err(e) = compiled {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": e.to_string()
+        }));
+    }
+
     let path = config_path.lock().unwrap().clone();
     match save_config_to_path(&new_config, &path) {
         Ok(()) => HttpResponse::Ok().json(serde_json::json!({
@@ -193,6 +207,65 @@ async fn api_list_channels() -> impl Responder {
     }))
 }
 
+/// GET /api/status - Circuit breaker state of channels that failed recently
+#[actix_web::get("/api/status")]
+async fn api_channel_status(config_path: web::Data<Mutex<PathBuf>>) -> impl Responder {
+    let path = config_path.lock().unwrap().clone();
+    let app_config = match load_config_from_path(&path) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to load config: {}", e)
+            }));
+        }
+    };
+
+    match StateStore::default().load() {
+        Ok(state) => {
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            HttpResponse::Ok().json(serde_json::json!({
+                "circuits": circuit_status(&state.circuits, &app_config.circuit_breaker, now_ms)
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to load state: {}", e)
+        })),
+    }
+}
+
+/// POST /api/explain - Show how the saved routing rules handle a hook input
+#[actix_web::post("/api/explain")]
+async fn api_explain(
+    config_path: web::Data<Mutex<PathBuf>>,
+    input: web::Json<HookInput>,
+) -> impl Responder {
+    let path = config_path.lock().unwrap().clone();
+    let app_config = match load_config_from_path(&path) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to load config: {}", e)
+            }));
+        }
+    };
+
+    // Rendering Stop messages reads the transcript, so keep it off the executor
+    let result = web::block(move || {
+        crate::ChannelManager::from_config(app_config)?.preview(&input.into_inner())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(preview)) => HttpResponse::Ok().json(preview),
+        Ok(Err(e)) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": e.to_string()
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": e.to_string()
+        })),
+    }
+}
+
 // Helper functions to avoid module import issues
 fn load_config_from_path(path: &PathBuf) -> Result<AppConfig, String> {
     crate::config::load_config_from_path(path).map_err(|e| e.to_string())
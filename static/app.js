@@ -1,6 +1,7 @@
 // Global state
 let config = null;
 let availableChannels = [];
+let circuits = {};
 
 // Channel icons and display names (sorted alphabetically except system first)
 const channelInfo = {
@@ -18,6 +19,13 @@ function getChannelDisplayName(channelId, channelConfig) {
     return channelConfig.name || info.name;
 }
 
+// Helper function to show user text inside HTML templates and attribute values
+function escapeHtml(text) {
+    const div = document.createElement('div');
+    div.textContent = text;
+    return div.innerHTML.replace(/"/g, '&quot;').replace(/'/g, '&#39;');
+}
+
 // Helper function to get sorted channel IDs (system first, then alphabetically by display name)
 function getSortedChannelIds() {
     if (!config || !config.channels) return [];
@@ -81,6 +89,7 @@ async function loadConfiguration() {
         config = await response.json();
         console.log('Configuration loaded:', config);
 
+        await loadChannelStatus();
         renderChannels();
         renderRoutingRules();
         renderTemplates();
@@ -92,6 +101,29 @@ async function loadConfiguration() {
     }
 }
 
+// Load circuit breaker state of channels that failed recently
+async function loadChannelStatus() {
+    try {
+        const response = await fetch('/api/status');
+        const data = await response.json();
+        circuits = data.circuits || {};
+    } catch (error) {
+        console.error('Failed to load channel status:', error);
+        circuits = {};
+    }
+}
+
+// Badge shown on channels that are being skipped after repeated failures
+function renderCircuitBadge(channelId) {
+    const circuit = circuits[channelId];
+    if (!circuit || !circuit.open) return '';
+
+    const until = new Date(circuit.open_until_ms).toLocaleTimeString();
+    const error = circuit.last_error ? ` Last error: ${circuit.last_error}` : '';
+    const title = `${circuit.consecutive_failures} consecutive failures.${error}`;
+    return `<span class="circuit-badge" title="${escapeHtml(title)}">⚠️ Skipped until ${escapeHtml(until)}</span>`;
+}
+
 // Render all channels
 function renderChannels() {
     const container = document.getElementById('channels-container');
@@ -114,6 +146,7 @@ function renderChannels() {
                     <span class="channel-icon">${info.icon}</span>
                     <span>${displayName}</span>
                     <span style="font-size: 0.8rem; color: #9ca3af; margin-left: 8px;">(${channelId})</span>
+                    ${renderCircuitBadge(channelId)}
                 </div>
                 <div class="checkbox-group">
                     <input
@@ -233,7 +266,7 @@ function renderRoutingRules() {
         ruleCard.className = 'routing-rule';
         ruleCard.innerHTML = `
             <div class="routing-rule-header">
-                <div class="routing-rule-title">${rule.name}</div>
+                <div class="routing-rule-title">${rule.name}${rule.default ? ' (default)' : ''}${rule.priority ? ` · priority ${rule.priority}` : ''}${rule.stop ? ' · stop' : ''}</div>
                 <div>
                     <input
                         type="checkbox"
@@ -269,6 +302,82 @@ function renderRoutingRules() {
                         <div style="font-size: 0.9rem; font-family: monospace;">${rule.match.tool_pattern}</div>
                     </div>
                 ` : ''}
+                ${rule.exclude_channels && rule.exclude_channels.length > 0 ? `
+                    <div>
+                        <label>Excluded Channels</label>
+                        <div style="font-size: 0.9rem;">${escapeHtml(rule.exclude_channels.join(', '))}</div>
+                    </div>
+                ` : ''}
+                ${rule.match.tool_input && rule.match.tool_input.length > 0 ? `
+                    <div>
+                        <label>Tool Input</label>
+                        ${rule.match.tool_input.map(c => `
+                            <div style="font-size: 0.9rem; font-family: monospace;">${escapeHtml(c.path)} ${c.glob ? 'glob' : '=~'} ${escapeHtml(c.glob || c.regex || '')}</div>
+                        `).join('')}
+                    </div>
+                ` : ''}
+                ${rule.match.status && rule.match.status.length > 0 ? `
+                    <div>
+                        <label>Status</label>
+                        <div style="font-size: 0.9rem;">${escapeHtml(rule.match.status.join(', '))}</div>
+                    </div>
+                ` : ''}
+                ${rule.match.min_severity || rule.severity ? `
+                    <div>
+                        <label>Severity</label>
+                        <div style="font-size: 0.9rem;">${escapeHtml([rule.match.min_severity ? `at least ${rule.match.min_severity}` : '', rule.severity ? `set to ${rule.severity}` : ''].filter(Boolean).join(', '))}</div>
+                    </div>
+                ` : ''}
+                ${rule.match.permission_modes && rule.match.permission_modes.length > 0 ? `
+                    <div>
+                        <label>Permission Modes</label>
+                        <div style="font-size: 0.9rem;">${escapeHtml(rule.match.permission_modes.join(', '))}</div>
+                    </div>
+                ` : ''}
+                ${rule.match.session_pattern ? `
+                    <div>
+                        <label>Session Pattern</label>
+                        <div style="font-size: 0.9rem; font-family: monospace;">${escapeHtml(rule.match.session_pattern)}</div>
+                    </div>
+                ` : ''}
+                ${rule.match.hostname ? `
+                    <div>
+                        <label>Hostname</label>
+                        <div style="font-size: 0.9rem; font-family: monospace;">${escapeHtml(rule.match.hostname)}</div>
+                    </div>
+                ` : ''}
+                ${rule.match.env && Object.keys(rule.match.env).length > 0 ? `
+                    <div>
+                        <label>Environment</label>
+                        ${Object.entries(rule.match.env).map(([name, pattern]) => `
+                            <div style="font-size: 0.9rem; font-family: monospace;">${escapeHtml(pattern === null ? `${name} unset` : `${name}=${pattern}`)}</div>
+                        `).join('')}
+                    </div>
+                ` : ''}
+                ${(rule.match.weekdays && rule.match.weekdays.length > 0) || (rule.match.time_window && rule.match.time_window.length > 0) ? `
+                    <div>
+                        <label>Time Window</label>
+                        <div style="font-size: 0.9rem;">${escapeHtml([...(rule.match.weekdays || []), ...(rule.match.time_window || []), rule.match.timezone || ''].join(' ').trim())}</div>
+                    </div>
+                ` : ''}
+                ${rule.match.cwd_pattern ? `
+                    <div>
+                        <label>Working Directory</label>
+                        <div style="font-size: 0.9rem; font-family: monospace;">${escapeHtml(rule.match.cwd_pattern)}</div>
+                    </div>
+                ` : ''}
+                ${rule.match.project ? `
+                    <div>
+                        <label>Project</label>
+                        <div style="font-size: 0.9rem; font-family: monospace;">${escapeHtml(rule.match.project)}</div>
+                    </div>
+                ` : ''}
+                ${rule.match.expression ? `
+                    <div>
+                        <label>Expression</label>
+                        <div style="font-size: 0.9rem; font-family: monospace;">${escapeHtml(rule.match.expression)}</div>
+                    </div>
+                ` : ''}
             </div>
         `;
         container.appendChild(ruleCard);
@@ -384,6 +493,64 @@ async function testChannel(channelId, channelType) {
     }
 }
 
+// Explain how the saved routing rules handle a hook input
+async function explainRouting() {
+    const container = document.getElementById('explain-result');
+    let input;
+    try {
+        input = JSON.parse(document.getElementById('explain-input').value);
+    } catch (error) {
+        showStatus(`Invalid JSON: ${error.message}`, 'error');
+        return;
+    }
+
+    try {
+        const response = await fetch('/api/explain', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify(input)
+        });
+        if (!response.ok) {
+            const text = await response.text();
+            let message = text;
+            try {
+                message = JSON.parse(text).error || text;
+            } catch (_) {}
+            showStatus(`Explain failed: ${message}`, 'error');
+            return;
+        }
+
+        const preview = await response.json();
+        const route = preview.route;
+        const outcomeLabels = { matched: '✅ matched', not_matched: '❌ no match', disabled: '⏸️ disabled', skipped: '⏭️ skipped' };
+        const rules = route.evaluations.map(e => {
+            let detail = e.reason ? `: ${escapeHtml(e.reason)}` : '';
+            if (e.outcome === 'matched' && !e.active) {
+                detail = ' (outside its schedule)';
+            }
+            return `<div style="font-size: 0.9rem;">${outcomeLabels[e.outcome] || e.outcome} <strong>${escapeHtml(e.rule)}</strong>${detail}</div>`;
+        }).join('') || '<div style="font-size: 0.9rem;">No routing rules</div>';
+        const messages = preview.messages.map(([channelId, message]) => message
+            ? `<div style="font-size: 0.9rem;"><strong>${escapeHtml(channelId)}</strong>: ${escapeHtml(message.title)} — ${escapeHtml(message.body)}</div>`
+            : `<div style="font-size: 0.9rem;"><strong>${escapeHtml(channelId)}</strong>: (unknown or disabled channel)</div>`
+        ).join('');
+
+        container.innerHTML = `
+            <div style="margin-top: 15px;">
+                <label>Rules</label>
+                ${rules}
+                <label style="margin-top: 10px;">Severity</label>
+                <div style="font-size: 0.9rem;">${escapeHtml(route.severity)}</div>
+                <label style="margin-top: 10px;">Channels${route.used_defaults ? ' (default channels)' : ''}</label>
+                ${messages || '<div style="font-size: 0.9rem;">None</div>'}
+            </div>
+        `;
+    } catch (error) {
+        console.error('Explain error:', error);
+        showStatus(`Explain failed: ${error.message}`, 'error');
+    }
+}
+
 // Save configuration
 async function saveConfiguration() {
     showStatus('Saving configuration...', 'loading');